@@ -1,15 +1,20 @@
-use crate::datum::Datum;
+use crate::datum::{self, wgs84_constants, Datum, DatumName};
+use crate::geodesic;
 use crate::math;
-use crate::mgrs::Mgrs;
-use crate::utm::Utm;
+use crate::mgrs::{self, Mgrs, MgrsError};
+use crate::projection::Projection;
+use crate::utm::{self, Utm};
 
 use std::f64::consts;
 use std::fmt;
+use std::str::FromStr;
 
 use num_complex::{Complex, Complex64};
+use thiserror::Error;
 
 /// Holds a pair for latitude and longitude coordinates
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Coord {
     /// Latitude: Must be contained in the interval [-90.0..90.0]
     pub lat: f64,
@@ -17,195 +22,3198 @@ pub struct Coord {
     pub lon: f64,
 }
 
+/// A Coord with an altitude above the ellipsoid, in meters
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoordAlt {
+    pub coord: Coord,
+    pub altitude: f64,
+}
+
+/// Errors produced when validating a Coord
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CoordError {
+    #[error("Latitude out of range: {0}")]
+    LatitudeOutOfRange(f64),
+    #[error("Longitude out of range: {0}")]
+    LongitudeOutOfRange(f64),
+}
+
+/// Errors produced when parsing a Coord from text
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CoordParseError {
+    #[error("Invalid number: {0}")]
+    InvalidNumber(String),
+    #[error("Expected latitude and longitude, found {0} components")]
+    WrongComponentCount(usize),
+    #[error("Latitude out of range: {0}")]
+    LatitudeOutOfRange(f64),
+    #[error("Longitude out of range: {0}")]
+    LongitudeOutOfRange(f64),
+    #[error("Minutes out of range: {0}")]
+    MinutesOutOfRange(f64),
+    #[error("Seconds out of range: {0}")]
+    SecondsOutOfRange(f64),
+    #[error("Invalid hemisphere: {0}")]
+    InvalidHemisphere(String),
+    #[error("Invalid Maidenhead locator length: {0}")]
+    InvalidLocatorLength(usize),
+    #[error("Invalid Maidenhead locator character: {0}")]
+    InvalidLocatorCharacter(char),
+}
+
+/// Errors produced when decoding a geohash
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum GeohashError {
+    #[error("Invalid geohash length: {0}")]
+    InvalidLength(usize),
+    #[error("Invalid geohash character: {0}")]
+    InvalidCharacter(char),
+}
+
+/// Errors produced when solving geodesics
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum GeodesicError {
+    #[error("Geodesic did not converge")]
+    NotConverged,
+}
+
+/// Solution of the inverse geodesic problem between two points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeodesicResult {
+    /// Length of the geodesic, in meters
+    pub distance_m: f64,
+    /// Bearing at the start point, in degrees clockwise from true north
+    pub initial_bearing: f64,
+    /// Bearing at the end point, in degrees clockwise from true north
+    pub final_bearing: f64,
+}
+
+/// Verdict on the scale distortion of a projection at a point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suitability {
+    /// Scale error below 0.1%, which covers a standard 6° UTM zone
+    Good,
+    /// Scale error below 0.2%, as in the widened zone 32V
+    Marginal,
+    /// Scale error of 0.2% or more
+    Poor,
+}
+
+impl Suitability {
+    /// Verdict for a point scale factor `k`.
+    fn from_scale_factor(k: f64) -> Suitability {
+        let error = (k - 1.0).abs();
+        if error < 0.001 {
+            Suitability::Good
+        } else if error < 0.002 {
+            Suitability::Marginal
+        } else {
+            Suitability::Poor
+        }
+    }
+}
+
 impl Coord {
     /// Return a new Coord instance.
     ///
     /// Latitude will be modular 90.0
     /// Longitude will be mobular 180.0
     pub fn new(mut lat: f64, mut lon: f64) -> Coord {
-        if lat < -90.0 || lat > 90.0 {
+        if !(-90.0..=90.0).contains(&lat) {
             lat %= 90.0;
         }
 
-        if lon < -180.0 || lon > 180.0 {
+        if !(-180.0..=180.0).contains(&lon) {
             lon %= 180.0;
         }
 
         Coord { lat, lon }
     }
-}
 
-impl fmt::Display for Coord {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "({}, {})", self.lat, self.lon)
+    /// Convert a UTM or UPS point back to geographic coordinates over the
+    /// ellipsoid of `datum`.
+    ///
+    /// The `From<Utm>` conversion is equivalent to calling this with
+    /// [`Datum::wgs84`].
+    pub fn from_utm_with_datum(utm: &Utm, datum: &Datum) -> Coord {
+        from_utm(utm, datum, utm::central_meridian(utm.zone))
     }
-}
 
-impl From<Mgrs> for Coord {
-    fn from(mgrs: Mgrs) -> Self {
-        let utm: Utm = mgrs.into();
-        utm.into()
+    /// Return a new Coord instance, rejecting values out of range.
+    ///
+    /// Unlike [`Coord::new`], values are not wrapped: the latitude must be
+    /// contained in the interval [-90.0..90.0] and the longitude in
+    /// [-180.0..180.0]. NaN is out of range.
+    pub fn try_new(lat: f64, lon: f64) -> Result<Coord, CoordError> {
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(CoordError::LatitudeOutOfRange(lat));
+        }
+        if !(-180.0..=180.0).contains(&lon) {
+            return Err(CoordError::LongitudeOutOfRange(lon));
+        }
+
+        Ok(Coord { lat, lon })
     }
-}
 
-impl From<Utm> for Coord {
-    fn from(utm: Utm) -> Self {
-        let latitude: f64;
-        let longitude: f64;
+    /// Parse the `lat,lon` pair of a map URL fragment.
+    ///
+    /// Anything up to the last `@` is skipped, as is any path or query after
+    /// the fragment. An optional third component, such as the `15z` zoom
+    /// level, is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let coord = coord::Coord::from_map_url_fragment("@-23.0095839,-43.4361816,15z").unwrap();
+    /// assert_eq!(coord.lat, -23.0095839);
+    /// ```
+    pub fn from_map_url_fragment(s: &str) -> Result<Coord, CoordParseError> {
+        let fragment = match s.rfind('@') {
+            Some(i) => &s[i + 1..],
+            None => s,
+        };
+        let fragment = fragment.split(['/', '?']).next().unwrap_or("").trim();
 
-        let easting = utm.easting;
-        let northing = utm.northing;
-        let north = utm.north;
-        let zone = utm.zone;
-        let ups = utm.ups;
+        let parts: Vec<&str> = fragment.split(',').map(str::trim).collect();
+        if parts.len() != 2 && parts.len() != 3 {
+            return Err(CoordParseError::WrongComponentCount(parts.len()));
+        }
 
-        let datum = Datum::wgs84();
-        let ind: usize = if ups { 0 } else { 2 } + if north { 1 } else { 0 };
-        let real_east: f64 = easting - datum.false_easting[ind];
-        let real_north: f64 = northing - datum.false_northing[ind];
+        parse_lat_lon(parts[0], parts[1])
+    }
 
-        if ups {
-            latitude = 0.0;
-            longitude = 0.0;
-        } else {
-            let lon_0: f64 = 6.0 * (zone as f64) - 183.0;
-            let mut xi: f64 = real_north / (datum.a1 * datum.k0);
-            let mut eta: f64 = real_east / (datum.a1 * datum.k0);
-
-            let xisign: f64 = if xi < 0.0 { -1.0 } else { 1.0 };
-            let etasign: f64 = if eta < 0.0 { -1.0 } else { 1.0 };
-            xi = xi * xisign;
-            eta = eta * etasign;
-
-            let backside: bool = xi > consts::PI / 2.0;
-            if backside {
-                xi = consts::PI - xi;
+    /// Parse a pair of degrees, minutes and seconds values, such as
+    /// `48°51'29.6"N 2°17'40.2"E`.
+    ///
+    /// Each value ends with its hemisphere letter, N, S, E or W, which gives
+    /// its sign; latitude and longitude may come in either order. Minutes
+    /// and seconds are optional and the ASCII `d`, `'` and `"` may replace
+    /// the degree, minute and second symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let coord = coord::Coord::from_dms("22d54'24.5\"S 43d10'22.4\"W").unwrap();
+    /// assert!((coord.lat + 22.906806).abs() < 1e-6);
+    /// ```
+    pub fn from_dms(s: &str) -> Result<Coord, CoordParseError> {
+        let mut values = Vec::new();
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            if "NSEWnsew".contains(c) {
+                values.push((&s[start..i], c.to_ascii_uppercase()));
+                start = i + c.len_utf8();
+            }
+        }
+        let rest = s[start..].trim_matches(|c: char| c == ',' || c.is_whitespace());
+        if !rest.is_empty() {
+            return Err(CoordParseError::InvalidHemisphere(rest.to_string()));
+        }
+        if values.len() != 2 {
+            return Err(CoordParseError::WrongComponentCount(values.len()));
+        }
+
+        let (mut lat, mut lon) = (None, None);
+        for &(text, hemisphere) in values.iter() {
+            let value = parse_dms(text)?;
+            let (slot, value) = match hemisphere {
+                'N' => (&mut lat, value),
+                'S' => (&mut lat, -value),
+                'E' => (&mut lon, value),
+                _ => (&mut lon, -value),
+            };
+            if slot.replace(value).is_some() {
+                return Err(CoordParseError::InvalidHemisphere(hemisphere.to_string()));
             }
+        }
+
+        match (lat, lon) {
+            (Some(lat), Some(lon)) => validate_lat_lon(lat, lon),
+            _ => unreachable!("two values with distinct axes"),
+        }
+    }
+
+    /// Format as degrees, minutes and seconds, such as
+    /// `23°00'34.50"S 43°26'10.25"W`.
+    ///
+    /// Seconds are rounded to two decimal places, carrying into the minutes
+    /// and degrees when needed. Zero latitude and longitude are labelled N
+    /// and E.
+    pub fn to_dms_string(&self) -> String {
+        format!(
+            "{}{} {}{}",
+            format_dms(self.lat),
+            if self.lat < 0.0 { 'S' } else { 'N' },
+            format_dms(self.lon),
+            if self.lon < 0.0 { 'W' } else { 'E' }
+        )
+    }
+
+    /// Maidenhead grid locator, such as `JN58td`, with `precision` pairs of
+    /// characters.
+    ///
+    /// The pairs alternate between field letters A to R, square digits,
+    /// subsquare letters a to x and extended square digits, each with the
+    /// longitude first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision` is not contained in the interval [1..5]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let coord = coord::Coord::new(48.14666, 11.60833);
+    /// assert_eq!(coord.to_maidenhead(3), "JN58td");
+    /// ```
+    pub fn to_maidenhead(&self, precision: usize) -> String {
+        assert!(
+            (1..=MAIDENHEAD_MAX_PAIRS).contains(&precision),
+            "invalid Maidenhead precision: {}",
+            precision
+        );
+
+        let mut lon = self.lon + 180.0;
+        let mut lat = self.lat + 90.0;
+        let (mut lon_size, mut lat_size) = (360.0, 180.0);
+        let mut locator = String::with_capacity(2 * precision);
+
+        for pair in 0..precision {
+            let (base, first) = maidenhead_pair(pair);
+            lon_size /= base as f64;
+            lat_size /= base as f64;
+            let x = ((lon / lon_size).floor() as u8).min(base - 1);
+            let y = ((lat / lat_size).floor() as u8).min(base - 1);
+            lon -= x as f64 * lon_size;
+            lat -= y as f64 * lat_size;
+            locator.push((first + x) as char);
+            locator.push((first + y) as char);
+        }
+
+        locator
+    }
+
+    /// Parse a Maidenhead grid locator, such as `JN58td`, into the center of
+    /// the square it names.
+    ///
+    /// Locators of 2 to 10 characters are supported. Letters are case
+    /// insensitive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let coord = coord::Coord::from_maidenhead("JN58td").unwrap();
+    /// assert!((coord.lat - 48.1458333).abs() < 1e-6);
+    /// assert!((coord.lon - 11.625).abs() < 1e-6);
+    /// ```
+    pub fn from_maidenhead(s: &str) -> Result<Coord, CoordParseError> {
+        if let Some(c) = s.chars().find(|c| !c.is_ascii()) {
+            return Err(CoordParseError::InvalidLocatorCharacter(c));
+        }
+        let bytes = s.as_bytes();
+        if bytes.is_empty() || bytes.len() % 2 == 1 || bytes.len() > 2 * MAIDENHEAD_MAX_PAIRS {
+            return Err(CoordParseError::InvalidLocatorLength(bytes.len()));
+        }
 
-            let c0: f64 = (2.0 * xi).cos();
-            let ch0: f64 = (2.0 * eta).cosh();
-            let s0: f64 = (2.0 * xi).sin();
-            let sh0: f64 = (2.0 * eta).sinh();
-
-            let mut a: Complex64 = Complex::new(2.0 * c0 * ch0, -2.0 * s0 * sh0);
-            let mut n = datum.maxpow;
-            let mut y0: Complex64 = Complex::new(if n == 0 { -datum.bet[n] } else { 0.0 }, 0.0);
-            let mut y1: Complex64 = Complex::new(0.0, 0.0);
-            let mut z0: Complex64 = Complex::new(
-                if n == 0 {
-                    -2.0 * n as f64 * datum.bet[n]
+        let (mut lon, mut lat) = (-180.0, -90.0);
+        let (mut lon_size, mut lat_size) = (360.0, 180.0);
+        for (pair, chars) in bytes.chunks(2).enumerate() {
+            let (base, first) = maidenhead_pair(pair);
+            lon_size /= base as f64;
+            lat_size /= base as f64;
+
+            let index = |c: u8| {
+                let i = c
+                    .to_ascii_lowercase()
+                    .wrapping_sub(first.to_ascii_lowercase());
+                if i < base {
+                    Ok(i as f64)
                 } else {
-                    0.0
-                },
-                0.0,
-            );
-            let mut z1: Complex64 = Complex::new(0.0, 0.0);
+                    Err(CoordParseError::InvalidLocatorCharacter(c as char))
+                }
+            };
+            lon += index(chars[0])? * lon_size;
+            lat += index(chars[1])? * lat_size;
+        }
 
-            if n == 0 {
-                n = n - 1;
-            }
+        Ok(Coord::new(lat + lat_size / 2.0, lon + lon_size / 2.0))
+    }
+
+    /// Geohash of `length` characters containing this point.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `length` is not contained in the interval [1..12]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let coord = coord::Coord::new(57.64911, 10.40744);
+    /// assert_eq!(coord.to_geohash(11), "u4pruydqqvj");
+    /// ```
+    pub fn to_geohash(&self, length: usize) -> String {
+        assert!(
+            (1..=GEOHASH_MAX_LENGTH).contains(&length),
+            "invalid geohash length: {}",
+            length
+        );
+
+        // Bits alternate between longitude and latitude, longitude first
+        let mut ranges = [(-180.0, 180.0), (-90.0, 90.0)];
+        let values = [self.lon, self.lat];
+        let mut geohash = String::with_capacity(length);
 
-            while n > 0 {
-                y1 = (a * y0) - (y1) - (datum.bet[n]);
-                z1 = (a * z0) - (z1) - (2.0 * (n as f64) * datum.bet[n]);
-                n = n - 1;
-                y0 = (a * y1) - (y0) - (datum.bet[n]);
-                z0 = (a * z1) - (z0) - (2.0 * (n as f64) * datum.bet[n]);
-                n = n - 1;
+        for i in 0..length {
+            let mut index = 0;
+            for bit in 0..5 {
+                let axis = (5 * i + bit) % 2;
+                let (min, max) = ranges[axis];
+                let mid = (min + max) / 2.0;
+                index <<= 1;
+                if values[axis] >= mid {
+                    index |= 1;
+                    ranges[axis].0 = mid;
+                } else {
+                    ranges[axis].1 = mid;
+                }
             }
+            geohash.push(GEOHASH_ALPHABET[index] as char);
+        }
 
-            a = Complex::new(s0 * ch0, c0 * sh0);
-            y1 = Complex::new(xi, eta) + a * y0;
+        geohash
+    }
 
-            let xip = y1.re;
-            let etap = y1.im;
-            let s = etap.sinh();
-            let c = xip.cos().max(0.0);
-            let r = s.hypot(c);
+    /// Center of the cell named by a geohash.
+    ///
+    /// Letters are case insensitive; `a`, `i`, `l` and `o` are not part of
+    /// the alphabet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let coord = coord::Coord::from_geohash("u4pruydqqvj").unwrap();
+    /// assert!((coord.lat - 57.64911).abs() < 1e-5);
+    /// assert!((coord.lon - 10.40744).abs() < 1e-5);
+    /// ```
+    pub fn from_geohash(s: &str) -> Result<Coord, GeohashError> {
+        Coord::from_geohash_with_error(s).map(|(coord, _, _)| coord)
+    }
 
-            let mut rlat: f64;
-            let mut rlon: f64;
+    /// Center of the cell named by a geohash, with the half height and half
+    /// width of the cell, in degrees.
+    ///
+    /// Every point of the cell is within these errors of the center in
+    /// latitude and longitude respectively.
+    pub fn from_geohash_with_error(s: &str) -> Result<(Coord, f64, f64), GeohashError> {
+        let length = s.chars().count();
+        if !(1..=GEOHASH_MAX_LENGTH).contains(&length) {
+            return Err(GeohashError::InvalidLength(length));
+        }
 
-            if r != 0.0 {
-                rlon = s.atan2(c).to_degrees();
-                let sxip = xip.sin();
-                let tau = math::tauf(sxip / r, datum.es);
-                rlat = tau.atan().to_degrees();
-            } else {
-                rlat = 90.0;
-                rlon = 0.0;
+        let mut ranges = [(-180.0, 180.0), (-90.0, 90.0)];
+        for (i, c) in s.chars().enumerate() {
+            let index = GEOHASH_ALPHABET
+                .iter()
+                .position(|&x| x as char == c.to_ascii_lowercase())
+                .ok_or(GeohashError::InvalidCharacter(c))?;
+            for bit in 0..5 {
+                let axis = (5 * i + bit) % 2;
+                let (min, max) = ranges[axis];
+                let mid = (min + max) / 2.0;
+                if index & (0b10000 >> bit) != 0 {
+                    ranges[axis].0 = mid;
+                } else {
+                    ranges[axis].1 = mid;
+                }
             }
+        }
+
+        let [(lon_min, lon_max), (lat_min, lat_max)] = ranges;
+        Ok((
+            Coord::new((lat_min + lat_max) / 2.0, (lon_min + lon_max) / 2.0),
+            (lat_max - lat_min) / 2.0,
+            (lon_max - lon_min) / 2.0,
+        ))
+    }
+
+    /// Great-circle distance, in meters, to another coordinate.
+    ///
+    /// Uses the haversine formula on a sphere with the WGS84 mean radius, so
+    /// it is a spherical approximation of the ellipsoidal distance.
+    pub fn haversine_distance(&self, other: &Coord) -> f64 {
+        let phi1 = self.lat.to_radians();
+        let phi2 = other.lat.to_radians();
+        let dphi = phi2 - phi1;
+        let dlambda = (other.lon - self.lon).to_radians();
+
+        let h = ((dphi / 2.0).sin().powi(2)
+            + phi1.cos() * phi2.cos() * (dlambda / 2.0).sin().powi(2))
+        .clamp(0.0, 1.0);
+
+        2.0 * h.sqrt().atan2((1.0 - h).sqrt()) * wgs84_constants::MEAN_RADIUS
+    }
+
+    /// Initial bearing, in degrees clockwise from true north, of the great
+    /// circle from this coordinate to `other`.
+    ///
+    /// Computed over a sphere and returned in the interval [0..360). The
+    /// bearing between identical points is 0.
+    pub fn initial_bearing(&self, other: &Coord) -> f64 {
+        bearing(self, other).rem_euclid(360.0)
+    }
+
+    /// Constant bearing, in degrees clockwise from true north, of the rhumb
+    /// line from this coordinate to `other`.
+    ///
+    /// Computed over a sphere and returned in the interval [0..360). The
+    /// shorter way around the globe is taken.
+    pub fn rhumb_bearing(&self, other: &Coord) -> f64 {
+        let phi1 = self.lat.to_radians();
+        let phi2 = other.lat.to_radians();
+        let dpsi =
+            ((consts::FRAC_PI_4 + phi2 / 2.0).tan() / (consts::FRAC_PI_4 + phi1 / 2.0).tan()).ln();
+        let dlambda = math::angle_diff(self.lon, other.lon).to_radians();
+
+        dlambda.atan2(dpsi).to_degrees().rem_euclid(360.0)
+    }
+
+    /// Rhumb bearing from this coordinate to `other`, expressed as a grid
+    /// bearing in this coordinate's UTM zone.
+    ///
+    /// This is [`Coord::rhumb_bearing`] corrected for the meridian
+    /// convergence at this point.
+    pub fn rhumb_grid_bearing(&self, other: &Coord) -> f64 {
+        let utm: Utm = (*self).into();
+        utm.true_to_grid_bearing(self.rhumb_bearing(other))
+    }
+
+    /// Points along the rhumb line leaving this coordinate at `bearing_deg`,
+    /// sampled every `step_deg` of longitude until `target_lon` is reached.
+    ///
+    /// Computed over a sphere. The line travels east or west according to the
+    /// bearing, crossing the antimeridian if needed, and both the start point
+    /// and the point at `target_lon` are included. A due north or due south
+    /// line never changes longitude, so only the start point and the pole it
+    /// leads to are returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step_deg` is not a positive, finite number
+    pub fn rhumb_line_to_longitude(
+        &self,
+        bearing_deg: f64,
+        target_lon: f64,
+        step_deg: f64,
+    ) -> Vec<Coord> {
+        assert!(
+            step_deg > 0.0 && step_deg.is_finite(),
+            "invalid step: {}",
+            step_deg
+        );
 
-            rlat = rlat * xisign;
-            if backside {
-                rlon = 180.0 - rlon;
+        let (sin_theta, cos_theta) = bearing_deg.to_radians().sin_cos();
+        if sin_theta.abs() < 1e-12 {
+            let pole = if cos_theta > 0.0 { 90.0 } else { -90.0 };
+            return vec![*self, Coord::new(pole, self.lon)];
+        }
+
+        let dlon = if sin_theta > 0.0 {
+            (target_lon - self.lon).rem_euclid(360.0)
+        } else {
+            -(self.lon - target_lon).rem_euclid(360.0)
+        };
+        let psi0 = (consts::FRAC_PI_4 + self.lat.to_radians() / 2.0).tan().ln();
+        let steps = (dlon.abs() / step_deg).ceil() as usize;
+
+        (0..=steps)
+            .map(|i| {
+                let d = if steps == 0 {
+                    0.0
+                } else {
+                    dlon * i as f64 / steps as f64
+                };
+                let psi = psi0 + d.to_radians() * cos_theta / sin_theta;
+                Coord {
+                    lat: psi.sinh().atan().to_degrees(),
+                    lon: math::angle_normalize(self.lon + d),
+                }
+            })
+            .collect()
+    }
+
+    /// Azimuth and elevation, in degrees, of `target` as seen from this
+    /// coordinate.
+    ///
+    /// Both points are placed on the WGS84 ellipsoid at the given heights and
+    /// the line of sight is expressed in the local east-north-up frame, so the
+    /// elevation accounts for the Earth curvature. The azimuth is clockwise
+    /// from true north in the interval [0..360); refraction is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let here = coord::Coord::new(-22.9519, -43.2105);
+    /// let (_, elevation) = here.look_angles(&here, 0.0, 1000.0);
+    /// assert_eq!(elevation.round(), 90.0);
+    /// ```
+    pub fn look_angles(
+        &self,
+        target: &Coord,
+        self_height_m: f64,
+        target_height_m: f64,
+    ) -> (f64, f64) {
+        let ellipsoid = DatumName::WGS84_ELLIPSOID;
+        let p = datum::to_geocentric(self, self_height_m, ellipsoid);
+        let q = datum::to_geocentric(target, target_height_m, ellipsoid);
+        let d = [q[0] - p[0], q[1] - p[1], q[2] - p[2]];
+
+        let (sphi, cphi) = self.lat.to_radians().sin_cos();
+        let (slam, clam) = self.lon.to_radians().sin_cos();
+        let east = dot(d, [-slam, clam, 0.0]);
+        let north = dot(d, [-sphi * clam, -sphi * slam, cphi]);
+        let up = dot(d, [cphi * clam, cphi * slam, sphi]);
+
+        let azimuth = east.atan2(north).to_degrees().rem_euclid(360.0);
+        let elevation = up.atan2(east.hypot(north)).to_degrees();
+
+        (azimuth, elevation)
+    }
+
+    /// Distance, in meters, to the geometric horizon seen from
+    /// `observer_height_m` meters above this coordinate.
+    ///
+    /// The Earth is taken as a sphere with the mean radius of `datum`, so
+    /// the result is `sqrt(2Rh + h²)`. Refraction is ignored; it typically
+    /// extends the visible horizon by a few percent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `observer_height_m` is negative
+    pub fn horizon_distance(&self, observer_height_m: f64, datum: &Datum) -> f64 {
+        assert!(
+            observer_height_m >= 0.0,
+            "invalid observer height: {}",
+            observer_height_m
+        );
+
+        let r = datum.mean_radius();
+        (2.0 * r * observer_height_m + observer_height_m.powi(2)).sqrt()
+    }
+
+    /// Destination reached from this coordinate following a great circle.
+    ///
+    /// `bearing_deg` is measured clockwise from true north and `distance_m` is
+    /// in meters over a sphere with the WGS84 mean radius.
+    pub fn destination(&self, bearing_deg: f64, distance_m: f64) -> Coord {
+        let phi1 = self.lat.to_radians();
+        let lambda1 = self.lon.to_radians();
+        let theta = bearing_deg.to_radians();
+        let delta = distance_m / wgs84_constants::MEAN_RADIUS;
+
+        let sphi2 =
+            (phi1.sin() * delta.cos() + phi1.cos() * delta.sin() * theta.cos()).clamp(-1.0, 1.0);
+        let phi2 = sphi2.asin();
+        let lambda2 = lambda1
+            + (theta.sin() * delta.sin() * phi1.cos()).atan2(delta.cos() - phi1.sin() * sphi2);
+
+        Coord {
+            lat: phi2.to_degrees().clamp(-90.0, 90.0),
+            lon: math::angle_normalize(lambda2.to_degrees()),
+        }
+    }
+
+    /// Point at `fraction` of the way along the great circle to `other`.
+    ///
+    /// A fraction of 0 returns this coordinate and 1 returns `other`. The
+    /// path is undefined for antipodal points, in which case this coordinate
+    /// is returned.
+    pub fn interpolate(&self, other: &Coord, fraction: f64) -> Coord {
+        let a = self.to_vector();
+        let b = other.to_vector();
+        let delta = dot(a, b).clamp(-1.0, 1.0).acos();
+        if delta < 1e-15 || (consts::PI - delta) < 1e-12 {
+            return *self;
+        }
+
+        let wa = ((1.0 - fraction) * delta).sin() / delta.sin();
+        let wb = (fraction * delta).sin() / delta.sin();
+
+        Coord::from_vector([
+            wa * a[0] + wb * b[0],
+            wa * a[1] + wb * b[1],
+            wa * a[2] + wb * b[2],
+        ])
+        .unwrap_or(*self)
+    }
+
+    /// Signed angular distance, in degrees, from this coordinate to the great
+    /// circle whose pole is `circle_pole`.
+    ///
+    /// Positive on the hemisphere containing the pole, negative on the other
+    /// one and zero on the great circle. With the north pole as
+    /// `circle_pole` the great circle is the equator and the result is the
+    /// latitude of this coordinate, on a sphere.
+    pub fn side_of_great_circle(&self, circle_pole: &Coord) -> f64 {
+        dot(self.to_vector(), circle_pole.to_vector())
+            .clamp(-1.0, 1.0)
+            .asin()
+            .to_degrees()
+    }
+
+    /// Minimal rotation mapping the surface normal of this coordinate to the
+    /// one of `other`, on a sphere.
+    ///
+    /// Returns the rotation axis, as the coordinate where it pierces the
+    /// sphere, and the counterclockwise angle in degrees about that axis.
+    /// For coincident or antipodal points the axis is not unique and any
+    /// axis perpendicular to this coordinate's normal is returned.
+    pub fn rotation_to(&self, other: &Coord) -> (Coord, f64) {
+        let a = self.to_vector();
+        let b = other.to_vector();
+        let axis = cross(a, b);
+        let angle = dot(axis, axis).sqrt().atan2(dot(a, b)).to_degrees();
+
+        let axis = Coord::from_vector(axis)
+            .or_else(|| Coord::from_vector(cross(a, [0.0, 0.0, 1.0])))
+            .or_else(|| Coord::from_vector(cross(a, [1.0, 0.0, 0.0])))
+            .unwrap_or(Coord { lat: 0.0, lon: 0.0 });
+
+        (axis, angle)
+    }
+
+    /// Polygon approximating a circle of `radius_m` meters around this coordinate.
+    ///
+    /// The vertices are computed with `destination` at evenly spaced bearings
+    /// starting from north. The ring is closed, so the first vertex is
+    /// repeated at the end.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `vertices` is less than 3
+    pub fn circle_polygon(&self, radius_m: f64, vertices: usize) -> Vec<Coord> {
+        assert!(vertices >= 3, "a polygon needs at least 3 vertices");
+
+        let step = 360.0 / vertices as f64;
+        let mut ring: Vec<Coord> = (0..vertices)
+            .map(|i| self.destination(i as f64 * step, radius_m))
+            .collect();
+        ring.push(ring[0]);
+
+        ring
+    }
+
+    /// Destination reached from this coordinate following a geodesic on the
+    /// ellipsoid of `datum`.
+    ///
+    /// Solved with Vincenty's direct formula. `bearing_deg` is measured
+    /// clockwise from true north and `distance_m` is in meters.
+    pub fn vincenty_destination(&self, bearing_deg: f64, distance_m: f64, datum: &Datum) -> Coord {
+        let (a, f) = (datum.a, datum.f);
+        let b = a * (1.0 - f);
+
+        let (sin_alpha1, cos_alpha1) = bearing_deg.to_radians().sin_cos();
+        let tan_u1 = (1.0 - f) * self.lat.to_radians().tan();
+        let cos_u1 = 1.0 / (1.0 + tan_u1.powi(2)).sqrt();
+        let sin_u1 = tan_u1 * cos_u1;
+
+        let sigma1 = tan_u1.atan2(cos_alpha1);
+        let sin_alpha = cos_u1 * sin_alpha1;
+        let cos2_alpha = 1.0 - sin_alpha.powi(2);
+        let u2 = cos2_alpha * (a.powi(2) - b.powi(2)) / b.powi(2);
+        let k_a = 1.0 + u2 / 16384.0 * (4096.0 + u2 * (-768.0 + u2 * (320.0 - 175.0 * u2)));
+        let k_b = u2 / 1024.0 * (256.0 + u2 * (-128.0 + u2 * (74.0 - 47.0 * u2)));
+
+        let mut sigma = distance_m / (b * k_a);
+        let mut cos_2sigma_m;
+        for _ in 0..100 {
+            cos_2sigma_m = (2.0 * sigma1 + sigma).cos();
+            let (sin_sigma, cos_sigma) = sigma.sin_cos();
+            let delta_sigma = k_b
+                * sin_sigma
+                * (cos_2sigma_m
+                    + k_b / 4.0
+                        * (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m.powi(2))
+                            - k_b / 6.0
+                                * cos_2sigma_m
+                                * (-3.0 + 4.0 * sin_sigma.powi(2))
+                                * (-3.0 + 4.0 * cos_2sigma_m.powi(2))));
+            let next = distance_m / (b * k_a) + delta_sigma;
+            let done = (next - sigma).abs() < 1e-12;
+            sigma = next;
+            if done {
+                break;
             }
-            rlon = rlon * etasign;
-            rlon = math::angle_normalize(rlon + lon_0);
+        }
+
+        cos_2sigma_m = (2.0 * sigma1 + sigma).cos();
+        let (sin_sigma, cos_sigma) = sigma.sin_cos();
+        let tmp = sin_u1 * sin_sigma - cos_u1 * cos_sigma * cos_alpha1;
+        let phi2 = (sin_u1 * cos_sigma + cos_u1 * sin_sigma * cos_alpha1)
+            .atan2((1.0 - f) * sin_alpha.hypot(tmp));
+        let lambda =
+            (sin_sigma * sin_alpha1).atan2(cos_u1 * cos_sigma - sin_u1 * sin_sigma * cos_alpha1);
+        let c = f / 16.0 * cos2_alpha * (4.0 + f * (4.0 - 3.0 * cos2_alpha));
+        let l = lambda
+            - (1.0 - c)
+                * f
+                * sin_alpha
+                * (sigma
+                    + c * sin_sigma
+                        * (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m.powi(2))));
 
-            latitude = rlat;
-            longitude = rlon;
+        Coord {
+            lat: phi2.to_degrees(),
+            lon: math::angle_normalize(self.lon + l.to_degrees()),
+        }
+    }
+
+    /// Distance and bearings of the geodesic to `other` on the ellipsoid of
+    /// `datum`.
+    ///
+    /// Solved with Karney's algorithm, which converges everywhere, including
+    /// for nearly antipodal points where Vincenty's inverse formula fails.
+    /// The distance is accurate to a few nanometers and the bearings are
+    /// returned in the interval [0..360). An error is only returned if no
+    /// finite solution is found, e.g. for a NaN coordinate.
+    pub fn geodesic_inverse(
+        &self,
+        other: &Coord,
+        datum: &Datum,
+    ) -> Result<GeodesicResult, GeodesicError> {
+        let (distance_m, azi1, azi2) =
+            geodesic::inverse(datum, self.lat, self.lon, other.lat, other.lon);
+        if !distance_m.is_finite() {
+            return Err(GeodesicError::NotConverged);
         }
 
-        Coord::new(latitude, longitude)
+        Ok(GeodesicResult {
+            distance_m,
+            initial_bearing: azi1.rem_euclid(360.0),
+            final_bearing: azi2.rem_euclid(360.0),
+        })
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Length, in meters, of the geodesic to `other` on the ellipsoid of
+    /// `datum`.
+    ///
+    /// Solved with Vincenty's inverse formula, accurate to well under a
+    /// millimeter. The iteration is bounded: for nearly antipodal points,
+    /// where it may not converge, the estimate from the last iteration is
+    /// returned, or the spherical [`Coord::haversine_distance`] if that
+    /// estimate is unusable.
+    /// [`Coord::geodesic_inverse`] converges for any pair of points.
+    pub fn geodesic_distance(&self, other: &Coord, datum: &Datum) -> f64 {
+        match self.vincenty_inverse(other, datum) {
+            (geodesic, true) => geodesic.distance_m,
+            (geodesic, false) if geodesic.distance_m.is_finite() => geodesic.distance_m,
+            _ => self.haversine_distance(other),
+        }
+    }
 
-    #[test]
-    fn instantiate_coord() {
-        let lat: f64 = -23.0095839;
-        let lon: f64 = -43.4361816;
-        let coord = Coord::new(lat, lon);
-        assert_eq!(coord.lat, lat);
-        assert_eq!(coord.lon, lon);
+    /// Vincenty's inverse solution, together with whether the iteration
+    /// converged. Without convergence the result comes from the last
+    /// iteration.
+    fn vincenty_inverse(&self, other: &Coord, datum: &Datum) -> (GeodesicResult, bool) {
+        let (a, f) = (datum.a, datum.f);
+        let b = a * (1.0 - f);
+
+        let l = (other.lon - self.lon).to_radians();
+        let u1 = ((1.0 - f) * self.lat.to_radians().tan()).atan();
+        let u2 = ((1.0 - f) * other.lat.to_radians().tan()).atan();
+        let (sin_u1, cos_u1) = u1.sin_cos();
+        let (sin_u2, cos_u2) = u2.sin_cos();
+
+        let mut lambda = l;
+        let mut converged = false;
+        let (mut sin_sigma, mut cos_sigma, mut sigma) = (0.0, 1.0, 0.0);
+        let (mut cos2_alpha, mut cos_2sigma_m) = (1.0, 0.0);
+        for _ in 0..200 {
+            let (sin_lambda, cos_lambda) = lambda.sin_cos();
+            sin_sigma = (cos_u2 * sin_lambda).hypot(cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda);
+            if sin_sigma == 0.0 {
+                let geodesic = GeodesicResult {
+                    distance_m: 0.0,
+                    initial_bearing: 0.0,
+                    final_bearing: 0.0,
+                };
+                return (geodesic, true);
+            }
+            cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+            sigma = sin_sigma.atan2(cos_sigma);
+            let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+            cos2_alpha = 1.0 - sin_alpha.powi(2);
+            // Equatorial lines have cos2_alpha = 0
+            cos_2sigma_m = if cos2_alpha != 0.0 {
+                cos_sigma - 2.0 * sin_u1 * sin_u2 / cos2_alpha
+            } else {
+                0.0
+            };
+            let c = f / 16.0 * cos2_alpha * (4.0 + f * (4.0 - 3.0 * cos2_alpha));
+            let next = l
+                + (1.0 - c)
+                    * f
+                    * sin_alpha
+                    * (sigma
+                        + c * sin_sigma
+                            * (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m.powi(2))));
+            let done = (next - lambda).abs() < 1e-12;
+            lambda = next;
+            if done {
+                converged = true;
+                break;
+            }
+        }
+        let converged = converged && lambda.abs() <= consts::PI;
+
+        let u2 = cos2_alpha * (a.powi(2) - b.powi(2)) / b.powi(2);
+        let k_a = 1.0 + u2 / 16384.0 * (4096.0 + u2 * (-768.0 + u2 * (320.0 - 175.0 * u2)));
+        let k_b = u2 / 1024.0 * (256.0 + u2 * (-128.0 + u2 * (74.0 - 47.0 * u2)));
+        let delta_sigma = k_b
+            * sin_sigma
+            * (cos_2sigma_m
+                + k_b / 4.0
+                    * (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m.powi(2))
+                        - k_b / 6.0
+                            * cos_2sigma_m
+                            * (-3.0 + 4.0 * sin_sigma.powi(2))
+                            * (-3.0 + 4.0 * cos_2sigma_m.powi(2))));
+
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let alpha1 = (cos_u2 * sin_lambda).atan2(cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda);
+        let alpha2 = (cos_u1 * sin_lambda).atan2(-sin_u1 * cos_u2 + cos_u1 * sin_u2 * cos_lambda);
+
+        let geodesic = GeodesicResult {
+            distance_m: b * k_a * (sigma - delta_sigma),
+            initial_bearing: alpha1.to_degrees().rem_euclid(360.0),
+            final_bearing: alpha2.to_degrees().rem_euclid(360.0),
+        };
+
+        (geodesic, converged)
     }
 
-    #[test]
-    fn to_utm() {
-        let lat: f64 = 55.722682;
-        let lon: f64 = 37.640653;
-        let coord = Coord::new(lat, lon);
-        let utm: Utm = coord.into();
-        assert_eq!(utm.easting.trunc(), 414617.0);
-        assert_eq!(utm.northing.trunc(), 6176052.0);
-        assert_eq!(utm.north, true);
-        assert_eq!(utm.zone, 37);
-        assert_eq!(utm.band, 'U');
-        assert_eq!(coord.lat, lat);
-        assert_eq!(coord.lon, lon);
+    /// Polygon of points `radius_m` meters away from this coordinate along
+    /// geodesics on the ellipsoid of `datum`.
+    ///
+    /// The ellipsoidal counterpart of [`Coord::circle_polygon`]: vertices are
+    /// computed with [`Coord::vincenty_destination`] at evenly spaced bearings
+    /// starting from north, and the ring is closed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `vertices` is less than 3
+    pub fn geodesic_circle(&self, radius_m: f64, vertices: usize, datum: &Datum) -> Vec<Coord> {
+        assert!(vertices >= 3, "a polygon needs at least 3 vertices");
+
+        let step = 360.0 / vertices as f64;
+        let mut ring: Vec<Coord> = (0..vertices)
+            .map(|i| self.vincenty_destination(i as f64 * step, radius_m, datum))
+            .collect();
+        ring.push(ring[0]);
+
+        ring
     }
 
-    #[test]
-    fn from_utm() {
-        let easting: f64 = 725641.61743212992;
-        let northing: f64 = 4911303.2874210617;
-        let north: bool = true;
-        let zone: i32 = 34;
-        let band: char = 'N';
-        let ups: bool = false;
+    /// Geocentric latitude, in degrees, of this coordinate on the ellipsoid of
+    /// `datum`.
+    ///
+    /// The geocentric latitude is the angle between the equator and the line
+    /// to the Earth's center, given by `tan(lat_gc) = (1 - f)² tan(lat)`.
+    pub fn geocentric_latitude(&self, datum: &Datum) -> f64 {
+        let phi = self.lat.to_radians();
+        ((1.0 - datum.f).powi(2) * phi.sin())
+            .atan2(phi.cos())
+            .to_degrees()
+    }
 
-        let lat: f64 = 44.319940;
-        let lon: f64 = 23.829616;
+    /// Coordinate with the given geocentric latitude and longitude, both in
+    /// degrees, on the ellipsoid of `datum`.
+    ///
+    /// Inverse of [`Coord::geocentric_latitude`].
+    pub fn from_geocentric_latitude(lat_gc: f64, lon: f64, datum: &Datum) -> Coord {
+        let psi = lat_gc.to_radians();
+        let lat = psi
+            .sin()
+            .atan2((1.0 - datum.f).powi(2) * psi.cos())
+            .to_degrees();
 
-        let utm: Utm = Utm::new(easting, northing, north, zone, band, ups);
+        Coord::new(lat, lon)
+    }
 
-        let coord: Coord = utm.into();
-        assert_eq!((coord.lat * 100.0).trunc(), (lat * 100.0).trunc());
-        assert_eq!((coord.lon * 100.0).trunc(), (lon * 100.0).trunc());
+    /// Whether both coordinates share the same MGRS label at precision `prec`.
+    ///
+    /// Returns false when either coordinate cannot be labelled, such as when
+    /// its latitude or longitude is not a finite number.
+    pub fn mgrs_eq(&self, other: &Coord, prec: usize) -> bool {
+        let label = |coord: &Coord| {
+            if !coord.lat.is_finite() || !coord.lon.is_finite() {
+                return None;
+            }
+            let mut mgrs: Mgrs = (*coord).into();
+            mgrs.prec = prec;
+            mgrs.to_mgrs_string().ok()
+        };
+
+        match (label(self), label(other)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
     }
 
-    #[test]
-    fn coord_clone() {
-        let lat: f64 = 75.11053;
-        let lon: f64 = 72.39391;
-        let mut coord_base = Coord::new(lat, lon);
-        let coord = coord_base.clone();
-        coord_base.lat = 0.0;
-        assert_eq!(coord.lat, lat);
+    /// UTM projection of this coordinate together with its MGRS label at
+    /// precision `prec`.
+    ///
+    /// The label is formatted from the returned UTM, so the point is only
+    /// projected once.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let coord = coord::Coord::new(-23.0095839, -43.4361816);
+    /// let (utm, mgrs) = coord.to_utm_and_mgrs(5).unwrap();
+    /// assert_eq!(utm.zone, 23);
+    /// assert_eq!(mgrs, "23KPQ6026554564");
+    /// ```
+    pub fn to_utm_and_mgrs(&self, prec: usize) -> Result<(Utm, String), MgrsError> {
+        if prec > mgrs::MAX_PRECISION {
+            return Err(MgrsError::InvalidPrecision(prec));
+        }
+
+        let utm: Utm = (*self).into();
+        let mgrs = Mgrs { utm, prec }.to_mgrs_string()?;
+
+        Ok((utm, mgrs))
+    }
+
+    /// Points along the great circle to `other`, spaced so the bearing
+    /// changes by at most `max_bearing_deviation_deg` along each leg.
+    ///
+    /// Legs are bisected until the difference between their initial and
+    /// final bearings is within the limit, so the path is denser where the
+    /// bearing turns quickly, such as near the poles. Both endpoints are
+    /// included. Bisection stops after 2^16 legs, and coincident or
+    /// antipodal endpoints return just the two points.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_bearing_deviation_deg` is not a positive number.
+    pub fn path_by_bearing(&self, other: &Coord, max_bearing_deviation_deg: f64) -> Vec<Coord> {
+        assert!(
+            max_bearing_deviation_deg > 0.0,
+            "invalid bearing deviation: {}",
+            max_bearing_deviation_deg
+        );
+
+        fn bisect(a: &Coord, b: &Coord, max_dev: f64, depth: u32, path: &mut Vec<Coord>) {
+            let turn = math::angle_diff(bearing(a, b), bearing(b, a) + 180.0).abs();
+            if depth < 16 && turn > max_dev {
+                let va = a.to_vector();
+                let vb = b.to_vector();
+                if let Some(mid) = Coord::from_vector([va[0] + vb[0], va[1] + vb[1], va[2] + vb[2]])
+                {
+                    bisect(a, &mid, max_dev, depth + 1, path);
+                    bisect(&mid, b, max_dev, depth + 1, path);
+                    return;
+                }
+            }
+            path.push(*b);
+        }
+
+        let mut path = vec![*self];
+        bisect(self, other, max_bearing_deviation_deg, 0, &mut path);
+        path
+    }
+
+    /// Project this coordinate to UTM and back, for diagnostics.
+    ///
+    /// Returns the reconstructed coordinate, the UTM zone used, and the
+    /// distance in meters between the original and reconstructed points.
+    pub fn round_trip_through_utm(&self) -> (Coord, i32, f64) {
+        let utm: Utm = (*self).into();
+        let back: Coord = utm.into();
+        let residual = self.haversine_distance(&back);
+
+        (back, utm.zone, residual)
+    }
+
+    /// UTM grid intersection nearest to this coordinate, for a grid of
+    /// `spacing_m` meters.
+    ///
+    /// The point is projected into its own zone and the easting and northing
+    /// are rounded to the nearest multiple of `spacing_m`, whereas
+    /// [`Utm::truncated`] floors them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `spacing_m` is not a positive, finite number
+    pub fn nearest_grid_intersection(&self, spacing_m: f64) -> Utm {
+        assert!(
+            spacing_m > 0.0 && spacing_m.is_finite(),
+            "invalid spacing: {}",
+            spacing_m
+        );
+
+        let utm: Utm = (*self).into();
+        Utm {
+            easting: (utm.easting / spacing_m).round() * spacing_m,
+            northing: (utm.northing / spacing_m).round() * spacing_m,
+            ..utm
+        }
+    }
+
+    /// Classify the UTM scale distortion at this coordinate.
+    ///
+    /// The point is projected into its own UTM zone and the departure from
+    /// unity of the WGS84 scale factor is compared against the thresholds of
+    /// [`Suitability`]. Standard zones are `Good` up to their edges on the
+    /// equator, while the western part of zone 32V is `Marginal`.
+    pub fn utm_suitability(&self) -> Suitability {
+        let utm: Utm = (*self).into();
+        Suitability::from_scale_factor(utm.scale_factor(Datum::wgs84_ref()))
+    }
+
+    /// Meridian convergence (degrees) and point scale factor when projecting
+    /// into the given UTM zone, computed with WGS84.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `zone` is not contained in the interval [1..60]
+    pub fn projection_factors(&self, zone: i32) -> (f64, f64) {
+        assert!((1..=60).contains(&zone), "invalid UTM zone: {}", zone);
+
+        let datum = Datum::wgs84_ref();
+        let (_, _, gamma, k) = utm::forward(datum, utm::central_meridian(zone), self.lat, self.lon);
+
+        (gamma, k)
+    }
+
+    /// Project into the given UTM zone, even if the point lies outside it.
+    ///
+    /// The projection uses the zone central meridian `6 * zone - 183` and
+    /// WGS84, so points in neighboring zones are projected with an easting
+    /// outside the usual [100000..900000] range. The returned `zone` is the
+    /// requested one and the band is taken from the latitude.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `zone` is not contained in the interval [1..60]
+    pub fn to_utm_zone(&self, zone: i32) -> Utm {
+        assert!((1..=60).contains(&zone), "invalid UTM zone: {}", zone);
+
+        let datum = Datum::wgs84_ref();
+        utm::project_to(datum, self, zone, utm::central_meridian(zone))
+    }
+
+    /// Return a copy with the longitude clamped into the 6° extent of a UTM zone.
+    ///
+    /// The extent is the zone central meridian ±3°. Latitude is unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `zone` is not contained in the interval [1..60]
+    pub fn clamp_to_zone(&self, zone: i32) -> Coord {
+        assert!((1..=60).contains(&zone), "invalid UTM zone: {}", zone);
+
+        let lon_0 = utm::central_meridian(zone);
+        let offset = math::angle_diff(lon_0, self.lon).clamp(-3.0, 3.0);
+
+        Coord::new(self.lat, math::angle_normalize(lon_0 + offset))
+    }
+
+    /// Longitude of the nearer of the two meridians bounding this point's
+    /// standard 6° UTM zone.
+    ///
+    /// The boundaries are the zone central meridian ±3°; the Norway and
+    /// Svalbard exceptions are not considered. A point on the central
+    /// meridian is equidistant to both and returns the western boundary.
+    pub fn nearest_zone_boundary_lon(&self) -> f64 {
+        let zone = ((self.lon + 180.0) / 6.0).floor() as i32 % 60 + 1;
+        let lon_0 = utm::central_meridian(zone);
+        let offset = if math::angle_diff(lon_0, self.lon) > 0.0 {
+            3.0
+        } else {
+            -3.0
+        };
+
+        math::angle_normalize(lon_0 + offset)
+    }
+
+    /// Fixed-width binary encoding of this coordinate.
+    ///
+    /// Bytes 0..8 hold the latitude and bytes 8..16 the longitude, both as
+    /// little-endian IEEE 754 doubles.
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.lat.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.lon.to_le_bytes());
+        bytes
+    }
+
+    /// Decode a coordinate written by [`Coord::to_bytes`].
+    ///
+    /// The values are restored bit for bit, without normalization.
+    pub fn from_bytes(bytes: &[u8; 16]) -> Coord {
+        let mut lat = [0; 8];
+        let mut lon = [0; 8];
+        lat.copy_from_slice(&bytes[..8]);
+        lon.copy_from_slice(&bytes[8..]);
+
+        Coord {
+            lat: f64::from_le_bytes(lat),
+            lon: f64::from_le_bytes(lon),
+        }
+    }
+
+    /// Coordinate from integer microdegrees (1e-6 degree).
+    ///
+    /// A microdegree of latitude is about 0.11 m on the ground.
+    pub fn from_microdegrees(lat_ud: i64, lon_ud: i64) -> Coord {
+        Coord::new(lat_ud as f64 / 1e6, lon_ud as f64 / 1e6)
+    }
+
+    /// Latitude and longitude rounded to integer microdegrees (1e-6 degree).
+    ///
+    /// The rounding error is at most half a microdegree, about 0.06 m.
+    pub fn to_microdegrees(&self) -> (i64, i64) {
+        (
+            (self.lat * 1e6).round() as i64,
+            (self.lon * 1e6).round() as i64,
+        )
+    }
+
+    /// Point where the great circle through this point and `other` reaches
+    /// the parallel `lat`.
+    ///
+    /// A great circle crosses a parallel twice or not at all. The crossing
+    /// lying on the minor arc between the two points is preferred, the one
+    /// nearer this point if both do; when neither does, the one nearer the
+    /// arc midpoint is returned. Returns
+    /// `None` if the great circle never reaches `lat`, or if the points are
+    /// coincident or antipodal so the great circle is undefined.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let a = coord::Coord::new(40.0, 0.0);
+    /// let b = coord::Coord::new(40.0, 180.0);
+    /// let crossing = a.crossing_latitude(&b, 60.0).unwrap();
+    /// assert_eq!(crossing.lon.round(), 0.0);
+    /// ```
+    pub fn crossing_latitude(&self, other: &Coord, lat: f64) -> Option<Coord> {
+        let a = self.to_vector();
+        let b = other.to_vector();
+        let n = cross(a, b);
+        let norm = dot(n, n).sqrt();
+        let r = n[0].hypot(n[1]);
+        if norm < 1e-12 || r < 1e-12 * norm {
+            return None;
+        }
+
+        let phi = lat.to_radians();
+        let cos_dlam = -n[2] * phi.tan() / r;
+        if !(-1.0..=1.0).contains(&cos_dlam) {
+            return None;
+        }
+
+        let theta = n[1].atan2(n[0]);
+        let dlam = cos_dlam.acos();
+        let within_arc =
+            |p: [f64; 3]| dot(cross(a, p), n) >= -1e-12 && dot(cross(p, b), n) >= -1e-12;
+        let mid = [a[0] + b[0], a[1] + b[1], a[2] + b[2]];
+
+        let candidates: Vec<Coord> = [theta + dlam, theta - dlam]
+            .iter()
+            .map(|lam| Coord::new(lat, math::angle_normalize(lam.to_degrees())))
+            .collect();
+        let nearest = |cs: Vec<Coord>, to: [f64; 3]| {
+            cs.into_iter().max_by(|x, y| {
+                dot(x.to_vector(), to)
+                    .partial_cmp(&dot(y.to_vector(), to))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+        };
+        let on_arc: Vec<Coord> = candidates
+            .iter()
+            .copied()
+            .filter(|c| within_arc(c.to_vector()))
+            .collect();
+        if on_arc.is_empty() {
+            nearest(candidates, mid)
+        } else {
+            nearest(on_arc, a)
+        }
+    }
+
+    /// Closest point to this coordinate on the great circle segment from `a`
+    /// to `b`, on a sphere.
+    ///
+    /// The foot of the perpendicular from this coordinate onto the great
+    /// circle through `a` and `b` is returned when it falls within the
+    /// segment, otherwise the nearest endpoint. Coincident or antipodal
+    /// endpoints leave the path undefined, in which case `a` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let a = coord::Coord::new(0.0, 0.0);
+    /// let b = coord::Coord::new(0.0, 10.0);
+    /// let snapped = coord::Coord::new(1.0, 5.0).nearest_on_path(&a, &b);
+    /// assert!(snapped.lat.abs() < 1e-9);
+    /// assert!((snapped.lon - 5.0).abs() < 1e-9);
+    /// ```
+    pub fn nearest_on_path(&self, a: &Coord, b: &Coord) -> Coord {
+        let va = a.to_vector();
+        let vb = b.to_vector();
+        let n = cross(va, vb);
+        let n2 = dot(n, n);
+        if n2 < 1e-24 {
+            return *a;
+        }
+
+        let p = self.to_vector();
+        let t = dot(p, n) / n2;
+        let foot = [p[0] - t * n[0], p[1] - t * n[1], p[2] - t * n[2]];
+        let within = dot(cross(va, foot), n) >= 0.0 && dot(cross(foot, vb), n) >= 0.0;
+
+        match Coord::from_vector(foot) {
+            Some(foot) if within => foot,
+            _ if dot(p, va) >= dot(p, vb) => *a,
+            _ => *b,
+        }
+    }
+
+    /// Unit vector of this coordinate on a sphere, in an earth-centered frame
+    pub(crate) fn to_vector(self) -> [f64; 3] {
+        let (sphi, cphi) = self.lat.to_radians().sin_cos();
+        let (slam, clam) = self.lon.to_radians().sin_cos();
+        [cphi * clam, cphi * slam, sphi]
+    }
+
+    /// Coordinate pointed by a vector in an earth-centered frame, or `None`
+    /// for a vector too close to zero
+    pub(crate) fn from_vector(v: [f64; 3]) -> Option<Coord> {
+        let norm = (v[0].powi(2) + v[1].powi(2) + v[2].powi(2)).sqrt();
+        if norm < 1e-12 {
+            return None;
+        }
+
+        Some(Coord {
+            lat: (v[2] / norm).clamp(-1.0, 1.0).asin().to_degrees(),
+            lon: v[1].atan2(v[0]).to_degrees(),
+        })
+    }
+}
+
+/// Initial great circle bearing, in degrees, from `a` to `b`
+fn bearing(a: &Coord, b: &Coord) -> f64 {
+    let phi1 = a.lat.to_radians();
+    let phi2 = b.lat.to_radians();
+    let dlambda = (b.lon - a.lon).to_radians();
+
+    let y = dlambda.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * dlambda.cos();
+
+    y.atan2(x).to_degrees()
+}
+
+/// Cross product of two vectors
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Dot product of two vectors
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.lat, self.lon)
+    }
+}
+
+impl CoordAlt {
+    /// Return a new CoordAlt instance.
+    pub fn new(coord: Coord, altitude: f64) -> CoordAlt {
+        CoordAlt { coord, altitude }
+    }
+
+    /// Horizontal position
+    pub fn coord(&self) -> Coord {
+        self.coord
+    }
+
+    /// Altitude above the ellipsoid, in meters
+    pub fn altitude(&self) -> f64 {
+        self.altitude
+    }
+}
+
+impl fmt::Display for CoordAlt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "({}, {}, {})",
+            self.coord.lat, self.coord.lon, self.altitude
+        )
+    }
+}
+
+/// Coord at altitude 0.0, on the ellipsoid surface
+impl From<Coord> for CoordAlt {
+    fn from(coord: Coord) -> Self {
+        CoordAlt::new(coord, 0.0)
+    }
+}
+
+/// Parse decimal degrees separated by a comma or by whitespace, such as
+/// `"-23.0095839, -43.4361816"` or `"-23.0095839 -43.4361816"`.
+impl FromStr for Coord {
+    type Err = CoordParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = if s.contains(',') {
+            s.split(',').map(str::trim).collect()
+        } else {
+            s.split_whitespace().collect()
+        };
+        if parts.len() != 2 {
+            return Err(CoordParseError::WrongComponentCount(parts.len()));
+        }
+
+        parse_lat_lon(parts[0], parts[1])
+    }
+}
+
+impl From<Mgrs> for Coord {
+    fn from(mgrs: Mgrs) -> Self {
+        let utm: Utm = mgrs.into();
+        utm.into()
+    }
+}
+
+impl From<Utm> for Coord {
+    fn from(utm: Utm) -> Self {
+        utm.unproject(Datum::wgs84_ref())
+    }
+}
+
+impl From<&Mgrs> for Coord {
+    fn from(mgrs: &Mgrs) -> Self {
+        Coord::from(&mgrs.utm)
+    }
+}
+
+impl From<&Utm> for Coord {
+    fn from(utm: &Utm) -> Self {
+        utm.unproject(Datum::wgs84_ref())
+    }
+}
+
+///
+/// Geographic centroid of a set of coordinates
+///
+/// Equivalent to `weighted_centroid` with unit weights.
+///
+pub fn centroid(points: &[Coord]) -> Option<Coord> {
+    let weighted: Vec<(Coord, f64)> = points.iter().map(|&p| (p, 1.0)).collect();
+    weighted_centroid(&weighted)
+}
+
+///
+/// Weighted geographic centroid of a set of coordinates
+///
+/// Each coordinate is converted to a unit vector on the sphere, scaled by
+/// its weight, and the normalized sum is converted back. Returns `None` for
+/// an empty input, a zero total weight, or when the weighted vectors cancel
+/// out (e.g. two antipodal points).
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let points = vec![
+///     (coord::Coord::new(0.0, 0.0), 1.0),
+///     (coord::Coord::new(0.0, 10.0), 3.0),
+/// ];
+/// let center = coord::weighted_centroid(&points).unwrap();
+/// assert!(center.lon > 5.0);
+/// ```
+///
+pub fn weighted_centroid(points: &[(Coord, f64)]) -> Option<Coord> {
+    let total: f64 = points.iter().map(|(_, w)| w).sum();
+    if points.is_empty() || total == 0.0 {
+        return None;
+    }
+
+    let mut sum = [0.0; 3];
+    for (point, weight) in points {
+        let v = point.to_vector();
+        for i in 0..3 {
+            sum[i] += v[i] * weight;
+        }
+    }
+
+    Coord::from_vector(sum)
+}
+
+/// Parse and validate a pair of decimal degree components
+fn parse_lat_lon(lat: &str, lon: &str) -> Result<Coord, CoordParseError> {
+    let lat: f64 = parse_degrees(lat)?;
+    let lon: f64 = parse_degrees(lon)?;
+
+    validate_lat_lon(lat, lon)
+}
+
+/// Check the ranges of a latitude and longitude pair
+fn validate_lat_lon(lat: f64, lon: f64) -> Result<Coord, CoordParseError> {
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err(CoordParseError::LatitudeOutOfRange(lat));
+    }
+    if !(-180.0..=180.0).contains(&lon) {
+        return Err(CoordParseError::LongitudeOutOfRange(lon));
+    }
+
+    Ok(Coord { lat, lon })
+}
+
+/// Format the absolute value of an angle in degrees as degrees, minutes and
+/// seconds with two decimal places
+fn format_dms(angle: f64) -> String {
+    let hundredths = (angle.abs() * 360000.0).round() as u64;
+    format!(
+        "{}\u{b0}{:02}'{:02}.{:02}\"",
+        hundredths / 360000,
+        hundredths / 6000 % 60,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
+/// Base 32 alphabet of geohashes
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+/// Length of the longest supported geohash
+const GEOHASH_MAX_LENGTH: usize = 12;
+
+/// Number of character pairs of the longest supported Maidenhead locator
+const MAIDENHEAD_MAX_PAIRS: usize = 5;
+
+/// Number of divisions and first character of a pair of a Maidenhead locator
+fn maidenhead_pair(pair: usize) -> (u8, u8) {
+    match pair {
+        0 => (18, b'A'),
+        _ if pair % 2 == 1 => (10, b'0'),
+        _ => (24, b'a'),
+    }
+}
+
+/// Parse an unsigned degrees, minutes and seconds value into degrees
+fn parse_dms(s: &str) -> Result<f64, CoordParseError> {
+    let text = s.trim_matches(|c: char| c == ',' || c.is_whitespace());
+    let cleaned: String = text
+        .chars()
+        .map(|c| match c {
+            '\u{b0}' | '\u{ba}' | 'd' | '\'' | '\u{2032}' | '"' | '\u{2033}' => ' ',
+            c => c,
+        })
+        .collect();
+    let parts = cleaned
+        .split_whitespace()
+        .map(parse_degrees)
+        .collect::<Result<Vec<f64>, _>>()?;
+    if parts.is_empty() || parts.len() > 3 || parts.iter().any(|&x| x < 0.0) {
+        return Err(CoordParseError::InvalidNumber(text.to_string()));
+    }
+
+    let minutes = parts.get(1).copied().unwrap_or(0.0);
+    let seconds = parts.get(2).copied().unwrap_or(0.0);
+    if minutes >= 60.0 {
+        return Err(CoordParseError::MinutesOutOfRange(minutes));
+    }
+    if seconds >= 60.0 {
+        return Err(CoordParseError::SecondsOutOfRange(seconds));
+    }
+
+    Ok(parts[0] + minutes / 60.0 + seconds / 3600.0)
+}
+
+/// Parse a finite decimal degree value
+fn parse_degrees(s: &str) -> Result<f64, CoordParseError> {
+    match s.parse::<f64>() {
+        Ok(x) if x.is_finite() => Ok(x),
+        _ => Err(CoordParseError::InvalidNumber(s.to_string())),
+    }
+}
+
+///
+/// Project every point into the same UTM zone
+///
+/// Points outside the zone are projected with its central meridian anyway,
+/// so eastings and northings can be compared directly across zone
+/// boundaries. Distortion grows quickly away from the zone, so this is meant
+/// for points in or near it.
+///
+/// # Panics
+///
+/// Panics if `zone` is not contained in the interval [1..60]
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let points = [coord::Coord::new(-23.0, -50.0), coord::Coord::new(-23.0, -40.0)];
+/// let utms = coord::to_common_zone(&points, 23);
+/// assert!(utms.iter().all(|utm| utm.zone == 23));
+/// ```
+///
+pub fn to_common_zone(points: &[Coord], zone: i32) -> Vec<Utm> {
+    assert!((1..=60).contains(&zone), "invalid UTM zone: {}", zone);
+
+    let datum = Datum::wgs84_ref();
+    let lon_0 = utm::central_meridian(zone);
+
+    points
+        .iter()
+        .map(|coord| utm::project_to(datum, coord, zone, lon_0))
+        .collect()
+}
+
+///
+/// Whether a point lies inside a ring with great circle edges
+///
+/// The bearings from `point` to the successive vertices are accumulated; they
+/// wind a full turn around points inside the ring and cancel out for points
+/// outside. Working with bearings makes the test independent of longitude
+/// wrap-around, so rings crossing the antimeridian or enclosing a pole are
+/// handled. The inside is taken as the side containing the centroid of the
+/// vertices, so rings must cover less than a hemisphere. The ring may be
+/// open or closed. Points on an edge may be reported either way.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let ring = [
+///     coord::Coord::new(-10.0, 170.0),
+///     coord::Coord::new(-10.0, -170.0),
+///     coord::Coord::new(10.0, -170.0),
+///     coord::Coord::new(10.0, 170.0),
+/// ];
+/// assert!(coord::point_in_polygon(&coord::Coord::new(0.0, 180.0), &ring));
+/// assert!(!coord::point_in_polygon(&coord::Coord::new(0.0, 0.0), &ring));
+/// ```
+///
+pub fn point_in_polygon(point: &Coord, ring: &[Coord]) -> bool {
+    if ring.len() < 3 {
+        return false;
+    }
+
+    let winding = |p: &Coord| -> f64 {
+        ring.iter()
+            .zip(ring.iter().cycle().skip(1))
+            .map(|(a, b)| math::angle_diff(bearing(p, a), bearing(p, b)))
+            .sum()
+    };
+
+    // A ring winds around both its inside and the antipodes of the inside,
+    // in opposite directions; the vertex centroid tells them apart
+    let turns = winding(point);
+    match centroid(ring) {
+        Some(center) => turns.abs() > 180.0 && turns.signum() == winding(&center).signum(),
+        None => turns.abs() > 180.0,
+    }
+}
+
+///
+/// Running great circle distance along a track
+///
+/// Element `i` is the distance, in meters, from the first point to point `i`
+/// following the track, computed with [`Coord::haversine_distance`]. The
+/// result has the same length as the input.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let track = [
+///     coord::Coord::new(0.0, 0.0),
+///     coord::Coord::new(0.0, 1.0),
+///     coord::Coord::new(1.0, 1.0),
+/// ];
+/// let distances = coord::cumulative_distances(&track);
+/// assert_eq!(distances[0], 0.0);
+/// ```
+///
+pub fn cumulative_distances(points: &[Coord]) -> Vec<f64> {
+    let mut total = 0.0;
+    let mut distances = Vec::with_capacity(points.len());
+    for (i, point) in points.iter().enumerate() {
+        if i > 0 {
+            total += points[i - 1].haversine_distance(point);
+        }
+        distances.push(total);
+    }
+
+    distances
+}
+
+///
+/// Length, in meters, of a polyline following geodesics on the ellipsoid
+///
+/// The segment lengths are solved with Karney's algorithm, as in
+/// [`Coord::geodesic_inverse`], which converges for any pair of points,
+/// including nearly antipodal ones. The spherical distances used by
+/// [`cumulative_distances`] can be off by up to about 0.5%, depending on the
+/// latitude and direction of travel.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let line = [coord::Coord::new(0.0, 0.0), coord::Coord::new(0.0, 1.0)];
+/// let length = coord::polyline_length_geodesic(&line, &datum::Datum::wgs84());
+/// assert_eq!(length.round(), 111319.0);
+/// ```
+///
+pub fn polyline_length_geodesic(points: &[Coord], datum: &Datum) -> f64 {
+    points
+        .windows(2)
+        .map(|edge| {
+            let (a, b) = (&edge[0], &edge[1]);
+            geodesic::inverse(datum, a.lat, a.lon, b.lat, b.lon).0
+        })
+        .sum()
+}
+
+///
+/// Insert points along the edges of a ring or polyline
+///
+/// Every edge longer than `max_segment_m` meters is split into equal great
+/// circle segments with [`Coord::interpolate`], so that no segment exceeds
+/// the limit. The original vertices are kept, in order.
+///
+/// # Panics
+///
+/// Panics if `max_segment_m` is not a positive number.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let line = [coord::Coord::new(0.0, 0.0), coord::Coord::new(0.0, 1.0)];
+/// let dense = coord::densify(&line, 10000.0);
+/// assert_eq!(dense.len(), 13);
+/// ```
+///
+pub fn densify(ring: &[Coord], max_segment_m: f64) -> Vec<Coord> {
+    assert!(
+        max_segment_m > 0.0,
+        "invalid segment length: {}",
+        max_segment_m
+    );
+
+    let mut dense = Vec::with_capacity(ring.len());
+    for edge in ring.windows(2) {
+        let segments = (edge[0].haversine_distance(&edge[1]) / max_segment_m)
+            .ceil()
+            .max(1.0) as usize;
+        dense.extend(
+            (0..segments).map(|i| edge[0].interpolate(&edge[1], i as f64 / segments as f64)),
+        );
+    }
+    dense.extend(ring.last());
+
+    dense
+}
+
+///
+/// Split a polyline where it crosses the antimeridian
+///
+/// Consecutive points more than 180° of longitude apart are taken to cross
+/// the antimeridian, the shorter way around. The great circle crossing point
+/// is inserted at the end of one sub-line, with longitude ±180 on the side of
+/// the preceding point, and at the start of the next one, on the other side.
+/// A polyline that does not cross is returned as a single sub-line, and an
+/// empty one as no sub-lines.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let line = [coord::Coord::new(0.0, 170.0), coord::Coord::new(0.0, -170.0)];
+/// let parts = coord::split_at_antimeridian(&line);
+/// assert_eq!(parts.len(), 2);
+/// assert_eq!(parts[0][1].lon, 180.0);
+/// assert_eq!(parts[1][0].lon, -180.0);
+/// ```
+///
+pub fn split_at_antimeridian(line: &[Coord]) -> Vec<Vec<Coord>> {
+    let mut parts = Vec::new();
+    let mut current: Vec<Coord> = line.iter().take(1).copied().collect();
+
+    for edge in line.windows(2) {
+        let (a, b) = (edge[0], edge[1]);
+        if crosses_antimeridian(&a, &b) {
+            let lat = antimeridian_crossing_lat(&a, &b);
+            let side = if a.lon > 0.0 { 180.0 } else { -180.0 };
+            current.push(Coord { lat, lon: side });
+            parts.push(current);
+            current = vec![Coord { lat, lon: -side }];
+        }
+        current.push(b);
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+
+    parts
+}
+
+/// Whether the shorter path between two points crosses the antimeridian
+fn crosses_antimeridian(a: &Coord, b: &Coord) -> bool {
+    (a.lon - b.lon).abs() > 180.0
+}
+
+/// Latitude where the great circle between two points reaches the
+/// antimeridian
+fn antimeridian_crossing_lat(a: &Coord, b: &Coord) -> f64 {
+    let n = cross(a.to_vector(), b.to_vector());
+    // Intersection with the plane of the 0/180 meridians, on the 180 side
+    let (x, z) = if n[2] > 0.0 {
+        (-n[2], n[0])
+    } else {
+        (n[2], -n[0])
+    };
+    if x.hypot(z) < 1e-12 {
+        return (a.lat + b.lat) / 2.0;
+    }
+
+    z.atan2(-x).to_degrees()
+}
+
+/// Inverse transverse mercator projection around the central meridian `lon_0`
+fn from_utm(utm: &Utm, datum: &Datum, lon_0: f64) -> Coord {
+    let (latitude, longitude, _, _) = reverse(utm, datum, lon_0);
+    Coord::new(latitude, longitude)
+}
+
+/// Inverse transverse mercator projection returning latitude, longitude,
+/// meridian convergence (degrees) and point scale factor
+pub(crate) fn reverse(utm: &Utm, datum: &Datum, lon_0: f64) -> (f64, f64, f64, f64) {
+    let ind: usize = if utm.ups { 0 } else { 2 } + if utm.north { 1 } else { 0 };
+    let real_east: f64 = utm.easting - datum.false_easting[ind];
+    let real_north: f64 = utm.northing - datum.false_northing[ind];
+
+    if utm.ups {
+        polar_inverse(datum, utm.north, real_east, real_north)
+    } else {
+        inverse(datum, lon_0, real_east, real_north)
+    }
+}
+
+/// Inverse transverse mercator projection around the central meridian
+/// `lon_0` of easting and northing without false origin, returning
+/// latitude, longitude, meridian convergence (degrees) and point scale factor
+pub(crate) fn inverse(
+    datum: &Datum,
+    lon_0: f64,
+    real_east: f64,
+    real_north: f64,
+) -> (f64, f64, f64, f64) {
+    let mut xi: f64 = real_north / (datum.a1 * datum.k0);
+    let mut eta: f64 = real_east / (datum.a1 * datum.k0);
+
+    let xisign: f64 = if xi < 0.0 { -1.0 } else { 1.0 };
+    let etasign: f64 = if eta < 0.0 { -1.0 } else { 1.0 };
+    xi *= xisign;
+    eta *= etasign;
+
+    let backside: bool = xi > consts::PI / 2.0;
+    if backside {
+        xi = consts::PI - xi;
+    }
+
+    let c0: f64 = (2.0 * xi).cos();
+    let ch0: f64 = (2.0 * eta).cosh();
+    let s0: f64 = (2.0 * xi).sin();
+    let sh0: f64 = (2.0 * eta).sinh();
+
+    let mut a: Complex64 = Complex::new(2.0 * c0 * ch0, -2.0 * s0 * sh0);
+    let mut n = datum.maxpow;
+    let mut y0: Complex64 = Complex::new(if n == 0 { -datum.bet[n] } else { 0.0 }, 0.0);
+    let mut y1: Complex64 = Complex::new(0.0, 0.0);
+    let mut z0: Complex64 = Complex::new(
+        if n == 0 {
+            -2.0 * n as f64 * datum.bet[n]
+        } else {
+            0.0
+        },
+        0.0,
+    );
+    let mut z1: Complex64 = Complex::new(0.0, 0.0);
+
+    if n == 0 {
+        n -= 1;
+    }
+
+    while n > 0 {
+        y1 = (a * y0) - (y1) - (datum.bet[n]);
+        z1 = (a * z0) - (z1) - (2.0 * (n as f64) * datum.bet[n]);
+        n -= 1;
+        y0 = (a * y1) - (y0) - (datum.bet[n]);
+        z0 = (a * z1) - (z0) - (2.0 * (n as f64) * datum.bet[n]);
+        n -= 1;
+    }
+
+    a /= 2.0;
+    z1 = 1.0 - z1 + z0 * a;
+    a = Complex::new(s0 * ch0, c0 * sh0);
+    y1 = Complex::new(xi, eta) + a * y0;
+
+    let mut rgamma: f64 = z1.im.atan2(z1.re).to_degrees();
+    let mut rk: f64 = datum.b1 / z1.norm();
+
+    let xip = y1.re;
+    let etap = y1.im;
+    let s = etap.sinh();
+    let c = xip.cos().max(0.0);
+    let r = s.hypot(c);
+
+    let mut rlat: f64;
+    let mut rlon: f64;
+
+    if r != 0.0 {
+        rlon = s.atan2(c).to_degrees();
+        let sxip = xip.sin();
+        let tau = math::tauf(sxip / r, datum.es);
+        rgamma += (sxip * etap.tanh()).atan2(c).to_degrees();
+        rlat = tau.atan().to_degrees();
+        rk *= (datum.e2m + datum.e2 / (1.0 + tau.powi(2))).sqrt() * 1.0_f64.hypot(tau) * r;
+    } else {
+        rlat = 90.0;
+        rlon = 0.0;
+        rk *= datum.c;
+    }
+
+    rlat *= xisign;
+    if backside {
+        rlon = 180.0 - rlon;
+        rgamma = 180.0 - rgamma;
+    }
+    rlon *= etasign;
+    rlon = math::angle_normalize(rlon + lon_0);
+
+    (
+        rlat,
+        rlon,
+        math::angle_normalize(rgamma * xisign * etasign),
+        rk * datum.k0,
+    )
+}
+
+/// Inverse polar stereographic projection centred on the north or south pole
+/// of easting and northing without false origin, returning latitude,
+/// longitude, meridian convergence (degrees) and point scale factor
+pub(crate) fn polar_inverse(
+    datum: &Datum,
+    north: bool,
+    real_east: f64,
+    real_north: f64,
+) -> (f64, f64, f64, f64) {
+    let rho = real_east.hypot(real_north);
+    let t = if rho != 0.0 {
+        rho / (2.0 * utm::UPS_K0 * datum.a / datum.c)
+    } else {
+        f64::EPSILON.powi(2)
+    };
+    let taup = (1.0 / t - t) / 2.0;
+    let tau = math::tauf(taup, datum.es);
+    let secphi = 1.0_f64.hypot(tau);
+
+    let k = if rho != 0.0 {
+        rho / datum.a * secphi * (datum.e2m + datum.e2 / secphi.powi(2)).sqrt()
+    } else {
+        utm::UPS_K0
+    };
+    let lat = tau.atan().to_degrees();
+    // Any longitude is valid at the pole itself
+    let lon = if rho != 0.0 {
+        real_east
+            .atan2(if north { -real_north } else { real_north })
+            .to_degrees()
+    } else {
+        0.0
+    };
+    let gamma = math::angle_normalize(if north { lon } else { -lon });
+
+    (if north { lat } else { -lat }, lon, gamma, k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instantiate_coord() {
+        let lat: f64 = -23.0095839;
+        let lon: f64 = -43.4361816;
+        let coord = Coord::new(lat, lon);
+        assert_eq!(coord.lat, lat);
+        assert_eq!(coord.lon, lon);
+    }
+
+    #[test]
+    fn try_new() {
+        let coord = Coord::try_new(-23.0095839, -43.4361816).unwrap();
+        assert_eq!((coord.lat, coord.lon), (-23.0095839, -43.4361816));
+        assert!(Coord::try_new(90.0, -180.0).is_ok());
+
+        assert_eq!(
+            Coord::try_new(100.0, 0.0).unwrap_err(),
+            CoordError::LatitudeOutOfRange(100.0)
+        );
+        assert_eq!(
+            Coord::try_new(0.0, -180.5).unwrap_err(),
+            CoordError::LongitudeOutOfRange(-180.5)
+        );
+        assert!(Coord::try_new(f64::NAN, 0.0).is_err());
+    }
+
+    #[test]
+    fn to_utm() {
+        let lat: f64 = 55.722682;
+        let lon: f64 = 37.640653;
+        let coord = Coord::new(lat, lon);
+        let utm: Utm = coord.into();
+        assert_eq!(utm.easting.trunc(), 414617.0);
+        assert_eq!(utm.northing.trunc(), 6176052.0);
+        assert!(utm.north);
+        assert_eq!(utm.zone, 37);
+        assert_eq!(utm.band, 'U');
+        assert_eq!(coord.lat, lat);
+        assert_eq!(coord.lon, lon);
+    }
+
+    #[test]
+    fn from_utm() {
+        let easting: f64 = 725_641.617_432_129_9;
+        let northing: f64 = 4_911_303.287_421_062;
+        let north: bool = true;
+        let zone: i32 = 34;
+        let band: char = 'N';
+        let ups: bool = false;
+
+        let lat: f64 = 44.319940;
+        let lon: f64 = 23.829616;
+
+        let utm: Utm = Utm::new(easting, northing, north, zone, band, ups);
+
+        let coord: Coord = utm.into();
+        assert_eq!((coord.lat * 100.0).trunc(), (lat * 100.0).trunc());
+        assert_eq!((coord.lon * 100.0).trunc(), (lon * 100.0).trunc());
+    }
+
+    #[test]
+    fn from_references() {
+        let utm = Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+        let coord = Coord::from(&utm);
+        assert_eq!((coord.lat * 1e4).trunc(), -230095.0);
+        assert_eq!(utm.easting, 660265.0);
+
+        let mgrs = Mgrs::new(utm);
+        let from_mgrs = Coord::from(&mgrs);
+        assert_eq!(from_mgrs.lat, coord.lat);
+        assert_eq!(from_mgrs.lon, coord.lon);
+        assert_eq!(mgrs.utm.zone, 23);
+    }
+
+    #[test]
+    fn from_ups() {
+        let cases = [
+            (87.0, 30.0, 'Z'),
+            (84.5, -120.0, 'Y'),
+            (-87.0, 30.0, 'B'),
+            (-85.0, -60.0, 'A'),
+            (-80.5, 179.9, 'B'),
+        ];
+        for &(lat, lon, band) in cases.iter() {
+            let utm: Utm = Coord::new(lat, lon).into();
+            assert_eq!(utm.band, band);
+            let coord: Coord = utm.into();
+            assert!((coord.lat - lat).abs() < 1e-6);
+            assert!((coord.lon - lon).abs() < 1e-6);
+        }
+
+        for &north in [true, false].iter() {
+            let pole = Utm::new(2000000.0, 2000000.0, north, 0, 'Z', true);
+            let coord: Coord = pole.into();
+            assert_eq!(coord.lat, if north { 90.0 } else { -90.0 });
+            assert_eq!(coord.lon, 0.0);
+        }
+    }
+
+    #[test]
+    fn from_utm_with_datum() {
+        let utm = Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+        let wgs84 = Coord::from_utm_with_datum(&utm, &Datum::wgs84());
+        let from: Coord = utm.into();
+        assert_eq!((wgs84.lat, wgs84.lon), (from.lat, from.lon));
+
+        let intl = Datum::from_epsg_ellipsoid(7022).unwrap();
+        let coord = Coord::from_utm_with_datum(&utm, &intl);
+        assert!((coord.lat - wgs84.lat).abs() > 1e-5);
+
+        let back = Utm::from_coord_with_datum(&coord, &intl);
+        assert!((back.easting - utm.easting).abs() < 1e-6);
+        assert!((back.northing - utm.northing).abs() < 1e-6);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        let json = serde_json::to_string(&coord).unwrap();
+        assert_eq!(json, r#"{"lat":-23.0095839,"lon":-43.4361816}"#);
+        let decoded: Coord = serde_json::from_str(&json).unwrap();
+        assert_eq!((decoded.lat, decoded.lon), (coord.lat, coord.lon));
+    }
+
+    #[test]
+    fn coord_clone() {
+        let lat: f64 = 75.11053;
+        let lon: f64 = 72.39391;
+        let mut coord_base = Coord::new(lat, lon);
+        let coord = coord_base;
+        coord_base.lat = 0.0;
+        assert_eq!(coord_base.lat, 0.0);
+        assert_eq!(coord.lat, lat);
         assert_eq!(coord.lon, lon);
     }
+
+    #[test]
+    fn to_utm_zone() {
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        let native = coord.to_utm_zone(23);
+        let from: Utm = coord.into();
+        assert_eq!(
+            (native.easting, native.northing),
+            (from.easting, from.northing)
+        );
+
+        let west = coord.to_utm_zone(22);
+        assert_eq!((west.zone, west.band, west.north), (22, 'K', false));
+        assert!(west.easting > 900000.0);
+        let back: Coord = west.into();
+        assert!((back.lat - coord.lat).abs() < 1e-9);
+        assert!((back.lon - coord.lon).abs() < 1e-9);
+
+        let east = Coord::new(51.0, 5.9).to_utm_zone(32);
+        assert!(east.easting < 300000.0);
+        let back: Coord = east.into();
+        assert!((back.lat - 51.0).abs() < 1e-9);
+        assert!((back.lon - 5.9).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn to_utm_zone_invalid_zone() {
+        Coord::new(0.0, 0.0).to_utm_zone(61);
+    }
+
+    #[test]
+    fn clamp_to_zone() {
+        let coord = Coord::new(-23.0, -41.0);
+        let clamped = coord.clamp_to_zone(23);
+        assert_eq!(clamped.lat, -23.0);
+        assert_eq!(clamped.lon, -42.0);
+
+        let coord = Coord::new(10.0, -179.5);
+        assert_eq!(coord.clamp_to_zone(60).lon, 180.0);
+        assert_eq!(coord.clamp_to_zone(1).lon, -179.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn clamp_to_invalid_zone() {
+        Coord::new(0.0, 0.0).clamp_to_zone(61);
+    }
+
+    #[test]
+    fn from_map_url_fragment() {
+        let coord = Coord::from_map_url_fragment("@-23.0095839,-43.4361816,15z").unwrap();
+        assert_eq!(coord.lat, -23.0095839);
+        assert_eq!(coord.lon, -43.4361816);
+
+        let url = "https://www.google.com/maps/@52.517153,13.412389,17z/data=!3m1";
+        let coord = Coord::from_map_url_fragment(url).unwrap();
+        assert_eq!(coord.lat, 52.517153);
+        assert_eq!(coord.lon, 13.412389);
+
+        assert_eq!(
+            Coord::from_map_url_fragment("@-23.0095839").unwrap_err(),
+            CoordParseError::WrongComponentCount(1)
+        );
+        assert_eq!(
+            Coord::from_map_url_fragment("@95.0,10.0,15z").unwrap_err(),
+            CoordParseError::LatitudeOutOfRange(95.0)
+        );
+        assert_eq!(
+            Coord::from_map_url_fragment("@abc,10.0").unwrap_err(),
+            CoordParseError::InvalidNumber("abc".to_string())
+        );
+    }
+
+    #[test]
+    fn from_str() {
+        let coord: Coord = "-23.0095839, -43.4361816".parse().unwrap();
+        assert_eq!((coord.lat, coord.lon), (-23.0095839, -43.4361816));
+        let coord: Coord = "-23.0095839 -43.4361816".parse().unwrap();
+        assert_eq!((coord.lat, coord.lon), (-23.0095839, -43.4361816));
+        let coord: Coord = "  52.5 ,13.4\n".parse().unwrap();
+        assert_eq!((coord.lat, coord.lon), (52.5, 13.4));
+        let coord: Coord = "\t52.5 \t 13.4 ".parse().unwrap();
+        assert_eq!((coord.lat, coord.lon), (52.5, 13.4));
+
+        assert_eq!(
+            "52.5".parse::<Coord>().unwrap_err(),
+            CoordParseError::WrongComponentCount(1)
+        );
+        assert_eq!(
+            "52.5, 13.4, 10".parse::<Coord>().unwrap_err(),
+            CoordParseError::WrongComponentCount(3)
+        );
+        assert_eq!(
+            "".parse::<Coord>().unwrap_err(),
+            CoordParseError::WrongComponentCount(0)
+        );
+        assert_eq!(
+            "north, east".parse::<Coord>().unwrap_err(),
+            CoordParseError::InvalidNumber("north".to_string())
+        );
+        assert_eq!(
+            "52.5, NaN".parse::<Coord>().unwrap_err(),
+            CoordParseError::InvalidNumber("NaN".to_string())
+        );
+        assert_eq!(
+            "10.0 190.0".parse::<Coord>().unwrap_err(),
+            CoordParseError::LongitudeOutOfRange(190.0)
+        );
+    }
+
+    #[test]
+    fn from_dms() {
+        let close =
+            |a: Coord, lat: f64, lon: f64| (a.lat - lat).abs() < 1e-6 && (a.lon - lon).abs() < 1e-6;
+
+        let eiffel = Coord::from_dms("48\u{b0}51'29.6\"N 2\u{b0}17'40.2\"E").unwrap();
+        assert!(close(eiffel, 48.858222, 2.2945));
+        let ascii = Coord::from_dms("48d51'29.6\"N, 2d17'40.2\"E").unwrap();
+        assert!(close(ascii, 48.858222, 2.2945));
+        let primes =
+            Coord::from_dms("48\u{b0}51\u{2032}29.6\u{2033}N 2\u{b0}17\u{2032}40.2\u{2033}E");
+        assert!(close(primes.unwrap(), 48.858222, 2.2945));
+
+        let rio = Coord::from_dms("22\u{b0}54'24.5\"S 43\u{b0}10'22.4\"W").unwrap();
+        assert!(close(rio, -22.906806, -43.172889));
+        let swapped = Coord::from_dms("43\u{b0}10'22.4\"W 22\u{b0}54'24.5\"S").unwrap();
+        assert!(close(swapped, -22.906806, -43.172889));
+
+        let zero = Coord::from_dms("10\u{b0}30'0\"N 20d0'0\"W").unwrap();
+        assert_eq!((zero.lat, zero.lon), (10.5, -20.0));
+        let degrees = Coord::from_dms("10\u{b0}S 20\u{b0}E").unwrap();
+        assert_eq!((degrees.lat, degrees.lon), (-10.0, 20.0));
+
+        assert_eq!(
+            Coord::from_dms("48\u{b0}60'0\"N 2\u{b0}0'0\"E").unwrap_err(),
+            CoordParseError::MinutesOutOfRange(60.0)
+        );
+        assert_eq!(
+            Coord::from_dms("48\u{b0}0'60\"N 2\u{b0}0'0\"E").unwrap_err(),
+            CoordParseError::SecondsOutOfRange(60.0)
+        );
+        assert_eq!(
+            Coord::from_dms("48\u{b0}N 2\u{b0}S").unwrap_err(),
+            CoordParseError::InvalidHemisphere("S".to_string())
+        );
+        assert_eq!(
+            Coord::from_dms("48\u{b0}N 2\u{b0}").unwrap_err(),
+            CoordParseError::InvalidHemisphere("2\u{b0}".to_string())
+        );
+        assert_eq!(
+            Coord::from_dms("48\u{b0}N").unwrap_err(),
+            CoordParseError::WrongComponentCount(1)
+        );
+        assert_eq!(
+            Coord::from_dms("95\u{b0}N 2\u{b0}E").unwrap_err(),
+            CoordParseError::LatitudeOutOfRange(95.0)
+        );
+        assert!(Coord::from_dms("x\u{b0}N 2\u{b0}E").is_err());
+    }
+
+    #[test]
+    fn to_dms_string() {
+        assert_eq!(
+            Coord::new(-23.0095839, -43.4361816).to_dms_string(),
+            "23\u{b0}00'34.50\"S 43\u{b0}26'10.25\"W"
+        );
+        assert_eq!(
+            Coord::new(48.858222, 2.2945).to_dms_string(),
+            "48\u{b0}51'29.60\"N 2\u{b0}17'40.20\"E"
+        );
+        assert_eq!(
+            Coord::new(10.999999, 0.0).to_dms_string(),
+            "11\u{b0}00'00.00\"N 0\u{b0}00'00.00\"E"
+        );
+        assert_eq!(
+            Coord::new(0.0000014, -179.9999986).to_dms_string(),
+            "0\u{b0}00'00.01\"N 179\u{b0}59'59.99\"W"
+        );
+
+        let coord = Coord::new(-33.8567844, 151.213108);
+        let parsed = Coord::from_dms(&coord.to_dms_string()).unwrap();
+        assert!((parsed.lat - coord.lat).abs() < 0.005 / 3600.0);
+        assert!((parsed.lon - coord.lon).abs() < 0.005 / 3600.0);
+    }
+
+    #[test]
+    fn to_maidenhead() {
+        let munich = Coord::new(48.14666, 11.60833);
+        assert_eq!(munich.to_maidenhead(1), "JN");
+        assert_eq!(munich.to_maidenhead(2), "JN58");
+        assert_eq!(munich.to_maidenhead(3), "JN58td");
+        assert_eq!(munich.to_maidenhead(4), "JN58td25");
+
+        let newington = Coord::new(41.714775, -72.727260);
+        assert_eq!(newington.to_maidenhead(3), "FN31pr");
+        let sydney = Coord::new(-33.8567844, 151.213108);
+        assert_eq!(sydney.to_maidenhead(4), "QF56od54");
+
+        assert_eq!(Coord::new(-90.0, -180.0).to_maidenhead(5), "AA00aa00aa");
+        assert_eq!(Coord::new(90.0, 180.0).to_maidenhead(3), "RR99xx");
+    }
+
+    #[test]
+    fn to_geohash() {
+        let coord = Coord::new(57.64911, 10.40744);
+        assert_eq!(coord.to_geohash(11), "u4pruydqqvj");
+        assert_eq!(coord.to_geohash(5), "u4pru");
+        assert_eq!(coord.to_geohash(1), "u");
+
+        assert_eq!(Coord::new(42.6, -5.6).to_geohash(5), "ezs42");
+        assert_eq!(Coord::new(-25.382708, -49.265506).to_geohash(8), "6gkzwgjz");
+        assert_eq!(Coord::new(0.0, 0.0).to_geohash(4), "s000");
+        assert_eq!(Coord::new(-90.0, -180.0).to_geohash(12), "000000000000");
+        assert_eq!(Coord::new(90.0, 180.0).to_geohash(12), "zzzzzzzzzzzz");
+    }
+
+    #[test]
+    #[should_panic]
+    fn to_geohash_invalid_length() {
+        Coord::new(0.0, 0.0).to_geohash(13);
+    }
+
+    #[test]
+    fn from_geohash() {
+        let (coord, lat_err, lon_err) = Coord::from_geohash_with_error("ezs42").unwrap();
+        assert!((coord.lat - 42.60498046875).abs() < 1e-12);
+        assert!((coord.lon + 5.60302734375).abs() < 1e-12);
+        assert_eq!(lat_err, 90.0 / 2.0_f64.powi(12));
+        assert_eq!(lon_err, 180.0 / 2.0_f64.powi(13));
+
+        let upper = Coord::from_geohash("EZS42").unwrap();
+        assert_eq!((upper.lat, upper.lon), (coord.lat, coord.lon));
+
+        for &c in ['a', 'i', 'l', 'o', '-'].iter() {
+            let geohash = format!("u4p{}", c);
+            assert_eq!(
+                Coord::from_geohash(&geohash).unwrap_err(),
+                GeohashError::InvalidCharacter(c)
+            );
+        }
+        assert_eq!(
+            Coord::from_geohash("").unwrap_err(),
+            GeohashError::InvalidLength(0)
+        );
+        assert_eq!(
+            Coord::from_geohash("u4pruydqqvjqq").unwrap_err(),
+            GeohashError::InvalidLength(13)
+        );
+    }
+
+    #[test]
+    fn geohash_round_trip() {
+        let coords = [
+            Coord::new(57.64911, 10.40744),
+            Coord::new(-23.0095839, -43.4361816),
+            Coord::new(-33.8567844, 151.213108),
+            Coord::new(89.99, -179.99),
+        ];
+        for coord in coords.iter() {
+            for length in 1..=12 {
+                let geohash = coord.to_geohash(length);
+                let (center, lat_err, lon_err) = Coord::from_geohash_with_error(&geohash).unwrap();
+                assert!((center.lat - coord.lat).abs() <= lat_err);
+                assert!((center.lon - coord.lon).abs() <= lon_err);
+                assert_eq!(center.to_geohash(length), geohash);
+            }
+        }
+    }
+
+    #[test]
+    fn from_maidenhead() {
+        let square = Coord::from_maidenhead("JN58").unwrap();
+        assert!((square.lat - 48.5).abs() < 1e-9);
+        assert!((square.lon - 11.0).abs() < 1e-9);
+        let field = Coord::from_maidenhead("jn").unwrap();
+        assert!((field.lat - 45.0).abs() < 1e-9);
+        assert!((field.lon - 10.0).abs() < 1e-9);
+
+        let lower = Coord::from_maidenhead("fn31pr").unwrap();
+        let upper = Coord::from_maidenhead("FN31PR").unwrap();
+        assert_eq!((lower.lat, lower.lon), (upper.lat, upper.lon));
+
+        for &(locator, c) in [
+            ("SA", 'S'),
+            ("JN5A", 'A'),
+            ("JN58yd", 'y'),
+            ("JN58td3x", 'x'),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                Coord::from_maidenhead(locator).unwrap_err(),
+                CoordParseError::InvalidLocatorCharacter(c)
+            );
+        }
+        assert_eq!(
+            Coord::from_maidenhead("J\u{f1}").unwrap_err(),
+            CoordParseError::InvalidLocatorCharacter('\u{f1}')
+        );
+        for &locator in ["", "J", "JN5", "JN58td25aa00"].iter() {
+            assert_eq!(
+                Coord::from_maidenhead(locator).unwrap_err(),
+                CoordParseError::InvalidLocatorLength(locator.len())
+            );
+        }
+    }
+
+    #[test]
+    fn maidenhead_round_trip() {
+        let coords = [
+            Coord::new(48.14666, 11.60833),
+            Coord::new(-23.0095839, -43.4361816),
+            Coord::new(-33.8567844, 151.213108),
+            Coord::new(64.1466, -21.9426),
+        ];
+        for coord in coords.iter() {
+            let (mut lon_size, mut lat_size) = (360.0, 180.0);
+            for pairs in 1..=5 {
+                let (base, _) = super::maidenhead_pair(pairs - 1);
+                lon_size /= base as f64;
+                lat_size /= base as f64;
+
+                let center = Coord::from_maidenhead(&coord.to_maidenhead(pairs)).unwrap();
+                assert!((center.lat - coord.lat).abs() <= lat_size / 2.0);
+                assert!((center.lon - coord.lon).abs() <= lon_size / 2.0);
+                assert_eq!(center.to_maidenhead(pairs), coord.to_maidenhead(pairs));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn to_maidenhead_invalid_precision() {
+        Coord::new(0.0, 0.0).to_maidenhead(0);
+    }
+
+    #[test]
+    fn haversine_distance() {
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        assert_eq!(coord.haversine_distance(&coord), 0.0);
+
+        // Published great circle distances between city centers
+        let pairs = [
+            ((51.5074, -0.1278), (48.8566, 2.3522), 343500.0),
+            ((40.7128, -74.0060), (34.0522, -118.2437), 3935800.0),
+            ((-22.9068, -43.1729), (-23.5505, -46.6333), 360700.0),
+        ];
+        for &((lat1, lon1), (lat2, lon2), expected) in pairs.iter() {
+            let a = Coord::new(lat1, lon1);
+            let b = Coord::new(lat2, lon2);
+            assert!((a.haversine_distance(&b) - expected).abs() < 300.0);
+            assert_eq!(a.haversine_distance(&b), b.haversine_distance(&a));
+        }
+
+        let antipodal = Coord::new(0.0, 0.0).haversine_distance(&Coord::new(0.0, 180.0));
+        assert!((antipodal - consts::PI * wgs84_constants::MEAN_RADIUS).abs() < 1e-6);
+        let poles = Coord::new(90.0, 0.0).haversine_distance(&Coord::new(-90.0, 0.0));
+        assert!(!poles.is_nan());
+        assert!((poles - antipodal).abs() < 1e-6);
+    }
+
+    #[test]
+    fn destination() {
+        let coord = Coord::new(0.0, 0.0);
+        let east = coord.destination(90.0, 111195.0);
+        assert!(east.lat.abs() < 1e-9);
+        assert!((east.lon - 1.0).abs() < 1e-4);
+
+        let start = Coord::new(-23.0095839, -43.4361816);
+        for &bearing in [0.0, 37.0, 90.0, 181.0, 300.0].iter() {
+            for &distance in [10.0, 25000.0, 1500000.0].iter() {
+                let dest = start.destination(bearing, distance);
+                assert!((start.haversine_distance(&dest) - distance).abs() < 1e-6);
+                let error = math::angle_diff(start.initial_bearing(&dest), bearing);
+                assert!(error.abs() < 1e-6);
+            }
+        }
+
+        // Across the antimeridian
+        let wrapped = Coord::new(0.0, 179.5).destination(90.0, 111195.0);
+        assert!((wrapped.lon + 179.5).abs() < 1e-4);
+
+        // Over the pole
+        let over = Coord::new(89.0, 0.0).destination(0.0, 222390.0);
+        assert!((over.lat - 89.0).abs() < 1e-3);
+        assert!((over.lon.abs() - 180.0).abs() < 1e-9);
+        let pole = Coord::new(89.0, 0.0).destination(0.0, 111195.0);
+        assert!(pole.lat <= 90.0);
+    }
+
+    #[test]
+    fn circle_polygon() {
+        let center = Coord::new(-23.0095839, -43.4361816);
+        let ring = center.circle_polygon(1000.0, 360);
+        assert_eq!(ring.len(), 361);
+        assert_eq!(ring[0].lat, ring[360].lat);
+        assert_eq!(ring[0].lon, ring[360].lon);
+        for vertex in ring.iter() {
+            assert!((center.haversine_distance(vertex) - 1000.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn circle_polygon_too_few_vertices() {
+        Coord::new(0.0, 0.0).circle_polygon(1000.0, 2);
+    }
+
+    #[test]
+    fn vincenty_destination() {
+        // Flinders Peak to Buninyong, from Vincenty (1975)
+        let dms = |d: f64, m: f64, s: f64| d.signum() * (d.abs() + m / 60.0 + s / 3600.0);
+        let flinders = Coord::new(dms(-37.0, 57.0, 3.72030), dms(144.0, 25.0, 29.52440));
+        let bearing = dms(306.0, 52.0, 5.37);
+        let dest = flinders.vincenty_destination(bearing, 54972.271, &Datum::wgs84());
+        assert!((dest.lat - dms(-37.0, 39.0, 10.15610)).abs() < 1e-8);
+        assert!((dest.lon - dms(143.0, 55.0, 35.38390)).abs() < 1e-8);
+
+        let same = flinders.vincenty_destination(45.0, 0.0, &Datum::wgs84());
+        assert!((same.lat - flinders.lat).abs() < 1e-12);
+        assert!((same.lon - flinders.lon).abs() < 1e-12);
+    }
+
+    #[test]
+    fn geodesic_inverse() {
+        // Flinders Peak to Buninyong, from Vincenty (1975)
+        let dms = |d: f64, m: f64, s: f64| d.signum() * (d.abs() + m / 60.0 + s / 3600.0);
+        let flinders = Coord::new(dms(-37.0, 57.0, 3.72030), dms(144.0, 25.0, 29.52440));
+        let buninyong = Coord::new(dms(-37.0, 39.0, 10.15610), dms(143.0, 55.0, 35.38390));
+        let result = flinders
+            .geodesic_inverse(&buninyong, &Datum::wgs84())
+            .unwrap();
+        assert!((result.distance_m - 54972.271).abs() < 1e-3);
+        assert!((result.initial_bearing - dms(306.0, 52.0, 5.37)).abs() < 1e-5);
+        assert!((result.final_bearing - dms(307.0, 10.0, 25.07)).abs() < 1e-5);
+
+        let same = flinders
+            .geodesic_inverse(&flinders, &Datum::wgs84())
+            .unwrap();
+        assert_eq!(same.distance_m, 0.0);
+
+        let equator = Coord::new(0.0, 0.0)
+            .geodesic_inverse(&Coord::new(0.0, 1.0), &Datum::wgs84())
+            .unwrap();
+        assert!((equator.distance_m - 111319.491).abs() < 1e-3);
+        assert!((equator.initial_bearing - 90.0).abs() < 1e-9);
+
+        // GeographicLib references: JFK to LHR, Wellington to Salamanca and
+        // the nearly antipodal example of Karney (2013), where Vincenty's
+        // iteration does not converge
+        let cases = [
+            (
+                40.6,
+                -73.8,
+                51.6,
+                -0.5,
+                5551759.400,
+                51.198882845,
+                107.821776735,
+            ),
+            (
+                -41.32,
+                174.81,
+                40.96,
+                -5.5,
+                19959679.267,
+                161.067669986,
+                18.825195123,
+            ),
+            (
+                -30.0,
+                0.0,
+                29.9,
+                179.8,
+                19989832.828,
+                161.890524736,
+                18.090737246,
+            ),
+        ];
+        for &(lat1, lon1, lat2, lon2, distance, azi1, azi2) in cases.iter() {
+            let result = Coord::new(lat1, lon1)
+                .geodesic_inverse(&Coord::new(lat2, lon2), &Datum::wgs84())
+                .unwrap();
+            assert!((result.distance_m - distance).abs() < 1e-3);
+            assert!((result.initial_bearing - azi1).abs() < 1e-9);
+            assert!((result.final_bearing - azi2).abs() < 1e-9);
+        }
+
+        // Equatorial points past the lift-off longitude leave the equator
+        let result = Coord::new(0.0, 0.0)
+            .geodesic_inverse(&Coord::new(0.0, 179.9), &Datum::wgs84())
+            .unwrap();
+        assert!(result.distance_m > 19990000.0 && result.distance_m < 20003931.46);
+        assert!(result.initial_bearing < 90.0);
+
+        let nan = Coord {
+            lat: f64::NAN,
+            lon: 0.0,
+        };
+        assert_eq!(
+            nan.geodesic_inverse(&flinders, &Datum::wgs84()),
+            Err(GeodesicError::NotConverged)
+        );
+    }
+
+    #[test]
+    fn geodesic_distance() {
+        let datum = Datum::wgs84();
+        let dms = |d: f64, m: f64, s: f64| d.signum() * (d.abs() + m / 60.0 + s / 3600.0);
+        let flinders = Coord::new(dms(-37.0, 57.0, 3.72030), dms(144.0, 25.0, 29.52440));
+        let buninyong = Coord::new(dms(-37.0, 39.0, 10.15610), dms(143.0, 55.0, 35.38390));
+        assert!((flinders.geodesic_distance(&buninyong, &datum) - 54972.271).abs() < 1e-3);
+        assert_eq!(flinders.geodesic_distance(&flinders, &datum), 0.0);
+
+        // Quarter meridian and half equator of WGS84
+        let meridian = Coord::new(0.0, 0.0).geodesic_distance(&Coord::new(90.0, 0.0), &datum);
+        assert!((meridian - 10001965.729).abs() < 1e-3);
+        let equator = Coord::new(0.0, 0.0).geodesic_distance(&Coord::new(0.0, 179.0), &datum);
+        assert!((equator - 19926188.852).abs() < 1e-3);
+
+        // Nearly antipodal points, where the iteration does not converge
+        let a = Coord::new(0.5, 0.0);
+        let b = Coord::new(-0.5, 179.7);
+        let distance = a.geodesic_distance(&b, &datum);
+        assert!(distance.is_finite());
+        assert!((distance / a.haversine_distance(&b) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn geodesic_circle() {
+        let center = Coord::new(70.0, 25.0);
+        let datum = Datum::wgs84();
+        let ellipsoidal = center.geodesic_circle(10000.0, 8, &datum);
+        let spherical = center.circle_polygon(10000.0, 8);
+        assert_eq!(ellipsoidal.len(), 9);
+        assert_eq!(ellipsoidal[0].lat, ellipsoidal[8].lat);
+
+        for (e, s) in ellipsoidal.iter().zip(spherical.iter()) {
+            let offset = e.haversine_distance(s);
+            assert!(offset > 1.0 && offset < 50.0);
+        }
+    }
+
+    #[test]
+    fn geocentric_latitude() {
+        let datum = Datum::wgs84();
+        let coord = Coord::new(45.0, 10.0);
+        let lat_gc = coord.geocentric_latitude(&datum);
+        assert_eq!((lat_gc * 100.0).round(), 4481.0);
+
+        let back = Coord::from_geocentric_latitude(lat_gc, 10.0, &datum);
+        assert!((back.lat - 45.0).abs() < 1e-12);
+        assert_eq!(back.lon, 10.0);
+
+        assert_eq!(Coord::new(0.0, 0.0).geocentric_latitude(&datum), 0.0);
+        assert!((Coord::new(90.0, 0.0).geocentric_latitude(&datum) - 90.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn side_of_great_circle() {
+        let north_pole = Coord::new(90.0, 0.0);
+        for &lat in [-90.0, -23.0095839, 0.0, 45.0, 89.0].iter() {
+            let side = Coord::new(lat, -43.4361816).side_of_great_circle(&north_pole);
+            assert!((side - lat).abs() < 1e-9);
+        }
+
+        // Great circle through the poles along the 0/180 meridians
+        let pole = Coord::new(0.0, 90.0);
+        assert!((Coord::new(10.0, 30.0).side_of_great_circle(&pole) - 30.0).abs() < 1.0);
+        assert!(Coord::new(10.0, -30.0).side_of_great_circle(&pole) < 0.0);
+        assert!(Coord::new(40.0, 180.0).side_of_great_circle(&pole).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rotation_to() {
+        let a = Coord::new(0.0, 0.0);
+        let (axis, angle) = a.rotation_to(&Coord::new(0.0, 90.0));
+        assert!((angle - 90.0).abs() < 1e-9);
+        assert!((axis.lat - 90.0).abs() < 1e-9);
+
+        let (axis, angle) = a.rotation_to(&Coord::new(90.0, 0.0));
+        assert!((angle - 90.0).abs() < 1e-9);
+        assert!(axis.lat.abs() < 1e-9);
+        assert!((axis.lon + 90.0).abs() < 1e-9);
+
+        let (axis, angle) = a.rotation_to(&a);
+        assert!(angle.abs() < 1e-9);
+        assert!(axis.side_of_great_circle(&a).abs() < 1e-9);
+
+        let (axis, angle) = a.rotation_to(&Coord::new(0.0, 180.0));
+        assert!((angle - 180.0).abs() < 1e-9);
+        assert!(axis.side_of_great_circle(&a).abs() < 1e-9);
+    }
+
+    #[test]
+    fn interpolate() {
+        let a = Coord::new(0.0, 0.0);
+        let b = Coord::new(0.0, 90.0);
+        assert!((a.interpolate(&b, 0.5).lon - 45.0).abs() < 1e-12);
+        assert_eq!(a.interpolate(&b, 0.0).lon, 0.0);
+        assert!((a.interpolate(&b, 1.0).lon - 90.0).abs() < 1e-12);
+
+        let c = Coord::new(-23.0095839, -43.4361816);
+        let d = Coord::new(51.4778, -0.0015);
+        let mid = c.interpolate(&d, 0.25);
+        let total = c.haversine_distance(&d);
+        assert!((c.haversine_distance(&mid) - total / 4.0).abs() < 1e-6);
+        assert!((mid.haversine_distance(&d) - total * 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn point_in_polygon() {
+        let ring = [
+            Coord::new(-24.0, -47.0),
+            Coord::new(-24.0, -43.0),
+            Coord::new(-22.0, -43.0),
+            Coord::new(-22.0, -47.0),
+            Coord::new(-24.0, -47.0),
+        ];
+        assert!(super::point_in_polygon(&Coord::new(-23.0, -45.0), &ring));
+        assert!(!super::point_in_polygon(&Coord::new(-23.0, -42.0), &ring));
+        assert!(!super::point_in_polygon(&Coord::new(23.0, 135.0), &ring));
+
+        // Reversed winding and across the antimeridian
+        let ring = [
+            Coord::new(10.0, 170.0),
+            Coord::new(10.0, -170.0),
+            Coord::new(-10.0, -170.0),
+            Coord::new(-10.0, 170.0),
+        ];
+        assert!(super::point_in_polygon(&Coord::new(0.0, 180.0), &ring));
+        assert!(super::point_in_polygon(&Coord::new(5.0, -175.0), &ring));
+        assert!(super::point_in_polygon(&Coord::new(-5.0, 175.0), &ring));
+        assert!(!super::point_in_polygon(&Coord::new(0.0, 160.0), &ring));
+        assert!(!super::point_in_polygon(&Coord::new(0.0, -160.0), &ring));
+        assert!(!super::point_in_polygon(&Coord::new(0.0, 0.0), &ring));
+
+        // Around the north pole
+        let cap: Vec<Coord> = (0..8)
+            .map(|i| Coord::new(80.0, -180.0 + 45.0 * i as f64))
+            .collect();
+        assert!(super::point_in_polygon(&Coord::new(85.0, 33.0), &cap));
+        assert!(!super::point_in_polygon(&Coord::new(75.0, 33.0), &cap));
+
+        assert!(!super::point_in_polygon(&Coord::new(0.0, 0.0), &ring[..2]));
+    }
+
+    #[test]
+    fn cumulative_distances() {
+        let track = [
+            Coord::new(0.0, 0.0),
+            Coord::new(0.0, 1.0),
+            Coord::new(1.0, 1.0),
+        ];
+        let d1 = track[0].haversine_distance(&track[1]);
+        let d2 = track[1].haversine_distance(&track[2]);
+        assert_eq!(super::cumulative_distances(&track), vec![0.0, d1, d1 + d2]);
+
+        assert!(super::cumulative_distances(&[]).is_empty());
+        assert_eq!(super::cumulative_distances(&track[..1]), vec![0.0]);
+    }
+
+    #[test]
+    fn polyline_length_geodesic() {
+        let datum = Datum::wgs84();
+        let track: Vec<Coord> = (0..=10)
+            .map(|i| Coord::new(-23.0 + i as f64, -43.0 + i as f64 / 2.0))
+            .collect();
+        let geodesic = super::polyline_length_geodesic(&track, &datum);
+        let spherical = super::cumulative_distances(&track)[10];
+        let ratio = geodesic / spherical;
+        assert!(ratio > 0.995 && ratio < 1.0);
+
+        // Every segment differs from the sphere by about the same ratio
+        for edge in track.windows(2) {
+            let segment = super::polyline_length_geodesic(edge, &datum);
+            let r = segment / edge[0].haversine_distance(&edge[1]);
+            assert!((r - ratio).abs() < 1e-3);
+        }
+
+        assert_eq!(super::polyline_length_geodesic(&track[..1], &datum), 0.0);
+
+        // Nearly antipodal leg, where Vincenty's iteration does not converge
+        let leg = [Coord::new(0.5, 0.0), Coord::new(-0.5, 179.7)];
+        let length = super::polyline_length_geodesic(&leg, &datum);
+        assert!((length - 19995624.890).abs() < 1e-3);
+    }
+
+    #[test]
+    fn densify() {
+        // One degree along the equator is about 111.2 km
+        let line = [
+            Coord::new(0.0, 0.0),
+            Coord::new(0.0, 1.0),
+            Coord::new(0.0, 1.05),
+        ];
+        let dense = super::densify(&line, 10000.0);
+        assert_eq!(dense.len(), 12 + 1 + 1);
+        assert_eq!(dense[12].lon, 1.0);
+        assert_eq!(dense[13].lon, 1.05);
+        for leg in dense.windows(2) {
+            assert!(leg[0].haversine_distance(&leg[1]) <= 10000.0);
+        }
+
+        assert!(super::densify(&[], 1000.0).is_empty());
+        assert_eq!(super::densify(&line[..1], 1000.0).len(), 1);
+    }
+
+    #[test]
+    fn split_at_antimeridian() {
+        let line = [Coord::new(0.0, 170.0), Coord::new(0.0, -170.0)];
+        let parts = super::split_at_antimeridian(&line);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].len(), 2);
+        assert_eq!(parts[1].len(), 2);
+        assert_eq!((parts[0][1].lat, parts[0][1].lon), (0.0, 180.0));
+        assert_eq!((parts[1][0].lat, parts[1][0].lon), (0.0, -180.0));
+
+        // Westward, off the equator, and back again
+        let line = [
+            Coord::new(-10.0, -175.0),
+            Coord::new(10.0, 175.0),
+            Coord::new(20.0, 170.0),
+            Coord::new(30.0, -170.0),
+        ];
+        let parts = super::split_at_antimeridian(&line);
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0][1].lon, -180.0);
+        assert!(parts[0][1].lat.abs() < 0.1);
+        assert_eq!(parts[1][0].lon, 180.0);
+        assert_eq!(parts[1].len(), 4);
+        assert_eq!(parts[1][3].lon, 180.0);
+        assert!(parts[1][3].lat > 20.0 && parts[1][3].lat < 30.0);
+        assert_eq!(parts[2][0].lat, parts[1][3].lat);
+
+        let line = [Coord::new(0.0, -10.0), Coord::new(0.0, 10.0)];
+        assert_eq!(super::split_at_antimeridian(&line).len(), 1);
+        assert!(super::split_at_antimeridian(&[]).is_empty());
+    }
+
+    #[test]
+    fn mgrs_eq() {
+        let a = Coord::new(-23.00958611, -43.43618250);
+        let b = Coord::new(-23.0097, -43.4358);
+        assert!(a.mgrs_eq(&b, 2));
+        assert!(!a.mgrs_eq(&b, 4));
+        assert!(!a.mgrs_eq(&Coord::new(f64::NAN, -43.4358), 2));
+    }
+
+    #[test]
+    fn nearest_zone_boundary_lon() {
+        assert_eq!(Coord::new(-23.0, -45.0).nearest_zone_boundary_lon(), -48.0);
+        assert_eq!(Coord::new(-23.0, -43.4).nearest_zone_boundary_lon(), -42.0);
+        assert_eq!(Coord::new(-23.0, -46.1).nearest_zone_boundary_lon(), -48.0);
+        assert_eq!(Coord::new(10.0, 179.0).nearest_zone_boundary_lon(), 180.0);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+
+        for _ in 0..1000 {
+            let coord = Coord::new(next() * 180.0 - 90.0, next() * 360.0 - 180.0);
+            let decoded = Coord::from_bytes(&coord.to_bytes());
+            assert_eq!(decoded.lat.to_bits(), coord.lat.to_bits());
+            assert_eq!(decoded.lon.to_bits(), coord.lon.to_bits());
+        }
+
+        let bytes = Coord::new(1.0, -2.0).to_bytes();
+        assert_eq!(&bytes[..8], &1.0f64.to_le_bytes());
+        assert_eq!(&bytes[8..], &(-2.0f64).to_le_bytes());
+    }
+
+    #[test]
+    fn microdegrees() {
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        assert_eq!(coord.to_microdegrees(), (-23009584, -43436182));
+
+        let decoded = Coord::from_microdegrees(-23009584, -43436182);
+        assert!((decoded.lat - coord.lat).abs() <= 0.5e-6);
+        assert!((decoded.lon - coord.lon).abs() <= 0.5e-6);
+        assert_eq!(decoded.to_microdegrees(), coord.to_microdegrees());
+
+        assert_eq!(
+            Coord::new(90.0, -180.0).to_microdegrees(),
+            (90000000, -180000000)
+        );
+    }
+
+    #[test]
+    fn nearest_on_path() {
+        let a = Coord::new(-23.0, -44.0);
+        let b = Coord::new(-22.0, -42.0);
+        let mid = a.interpolate(&b, 0.5);
+        let offset = mid.destination(bearing(&mid, &b) + 90.0, 5000.0);
+
+        let snapped = offset.nearest_on_path(&a, &b);
+        assert!(snapped.haversine_distance(&mid) < 1.0);
+        assert!((offset.haversine_distance(&snapped) - 5000.0).abs() < 1.0);
+
+        let before = a.destination(bearing(&a, &b) + 180.0, 10000.0);
+        let snapped = before.nearest_on_path(&a, &b);
+        assert_eq!((snapped.lat, snapped.lon), (a.lat, a.lon));
+        let snapped = Coord::new(-21.0, -41.0).nearest_on_path(&a, &b);
+        assert_eq!((snapped.lat, snapped.lon), (b.lat, b.lon));
+    }
+
+    #[test]
+    fn crossing_latitude() {
+        // Over the pole: the path leaves along lon 0 and comes back along 180
+        let a = Coord::new(40.0, 0.0);
+        let b = Coord::new(40.0, 180.0);
+        let crossing = a.crossing_latitude(&b, 60.0).unwrap();
+        assert!((crossing.lat - 60.0).abs() < 1e-9);
+        assert!(math::angle_diff(crossing.lon, 0.0).abs() < 1e-9);
+        let crossing = b.crossing_latitude(&a, 60.0).unwrap();
+        assert!(math::angle_diff(crossing.lon, 180.0).abs() < 1e-9);
+
+        let a = Coord::new(10.0, -30.0);
+        let b = Coord::new(50.0, 20.0);
+        let crossing = a.crossing_latitude(&b, 30.0).unwrap();
+        assert!(crossing.lon > -30.0 && crossing.lon < 20.0);
+        let total = a.haversine_distance(&b);
+        let split = a.haversine_distance(&crossing) + crossing.haversine_distance(&b);
+        assert!((total - split).abs() < 1e-6);
+
+        assert!(a.crossing_latitude(&b, 80.0).is_none());
+        assert!(a.crossing_latitude(&a, 30.0).is_none());
+    }
+
+    #[test]
+    fn to_utm_and_mgrs() {
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        let (utm, mgrs) = coord.to_utm_and_mgrs(5).unwrap();
+        assert_eq!(utm.easting.trunc(), 660265.0);
+        assert_eq!(utm.northing.trunc(), 7454564.0);
+        assert_eq!(utm.zone, 23);
+        assert_eq!(utm.band, 'K');
+        assert_eq!(mgrs, "23KPQ6026554564");
+
+        assert_eq!(coord.to_utm_and_mgrs(0).unwrap().1, "23KPQ");
+        assert_eq!(
+            coord.to_utm_and_mgrs(12).unwrap_err(),
+            MgrsError::InvalidPrecision(12)
+        );
+    }
+
+    #[test]
+    fn to_common_zone() {
+        let points = [
+            Coord::new(-23.0, -50.0),
+            Coord::new(-23.0, -45.0),
+            Coord::new(-23.0, -40.0),
+        ];
+        let utms = super::to_common_zone(&points, 23);
+        assert_eq!(utms.len(), 3);
+        assert!(utms.iter().all(|utm| utm.zone == 23 && !utm.north));
+        assert!(utms[0].easting < utms[1].easting && utms[1].easting < utms[2].easting);
+        assert_eq!(utms[1].easting.round(), 500000.0);
+        assert!((utms[0].northing - utms[2].northing).abs() < 1e-6);
+
+        let natural: Utm = points[1].into();
+        assert!((natural.northing - utms[1].northing).abs() < 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn to_common_zone_invalid_zone() {
+        super::to_common_zone(&[Coord::new(0.0, 0.0)], 61);
+    }
+
+    #[test]
+    fn initial_bearing() {
+        let origin = Coord::new(0.0, 0.0);
+        assert_eq!(origin.initial_bearing(&Coord::new(10.0, 0.0)), 0.0);
+        assert_eq!(origin.initial_bearing(&Coord::new(-10.0, 0.0)), 180.0);
+        assert_eq!(origin.initial_bearing(&Coord::new(0.0, 90.0)), 90.0);
+        assert_eq!(origin.initial_bearing(&Coord::new(0.0, -90.0)), 270.0);
+        assert_eq!(origin.initial_bearing(&origin), 0.0);
+
+        let rio = Coord::new(-23.0, -43.0);
+        assert_eq!(rio.initial_bearing(&Coord::new(-20.0, -43.0)), 0.0);
+        assert_eq!(rio.initial_bearing(&Coord::new(-26.0, -43.0)), 180.0);
+
+        let london = Coord::new(51.5074, -0.1278);
+        let paris = Coord::new(48.8566, 2.3522);
+        let new_york = Coord::new(40.7128, -74.0060);
+        assert!((london.initial_bearing(&paris) - 148.116).abs() < 1e-3);
+        assert!((new_york.initial_bearing(&london) - 51.213).abs() < 1e-3);
+    }
+
+    #[test]
+    fn rhumb_bearing() {
+        let a = Coord::new(0.0, 0.0);
+        assert_eq!(a.rhumb_bearing(&Coord::new(10.0, 0.0)), 0.0);
+        assert_eq!(a.rhumb_bearing(&Coord::new(0.0, 10.0)), 90.0);
+        assert_eq!(a.rhumb_bearing(&Coord::new(-10.0, 0.0)), 180.0);
+        assert_eq!(a.rhumb_bearing(&Coord::new(0.0, -10.0)), 270.0);
+
+        // Across the antimeridian the short way is east
+        let b = Coord::new(0.0, 179.0);
+        assert_eq!(b.rhumb_bearing(&Coord::new(0.0, -179.0)), 90.0);
+
+        // Dover to Calais, 116°38'10"
+        let dms = |d: f64, m: f64, s: f64| d + m / 60.0 + s / 3600.0;
+        let dover = Coord::new(dms(51.0, 7.0, 32.0), dms(1.0, 20.0, 17.0));
+        let calais = Coord::new(dms(50.0, 57.0, 48.0), dms(1.0, 51.0, 9.0));
+        let bearing = dover.rhumb_bearing(&calais);
+        assert!((bearing - dms(116.0, 38.0, 10.0)).abs() < 1.0 / 3600.0);
+    }
+
+    #[test]
+    fn rhumb_line_to_longitude() {
+        let start = Coord::new(-23.0, -43.0);
+        let line = start.rhumb_line_to_longitude(45.0, -40.0, 0.5);
+        assert_eq!(line.len(), 7);
+        assert!(line.windows(2).all(|w| w[1].lat > w[0].lat));
+        let end = line[line.len() - 1];
+        assert!((end.lon + 40.0).abs() < 1e-9);
+        assert!((start.rhumb_bearing(&end) - 45.0).abs() < 1e-9);
+
+        let wrapped = Coord::new(10.0, 179.0).rhumb_line_to_longitude(120.0, -179.0, 1.0);
+        assert_eq!(wrapped.len(), 3);
+        assert!((wrapped[1].lon.abs() - 180.0).abs() < 1e-9);
+        assert!((wrapped[2].lon + 179.0).abs() < 1e-9);
+        assert!(wrapped[2].lat < 10.0);
+
+        let west = start.rhumb_line_to_longitude(270.0, -44.0, 0.25);
+        assert_eq!(west.len(), 5);
+        assert!(west.iter().all(|c| (c.lat + 23.0).abs() < 1e-9));
+
+        let north = start.rhumb_line_to_longitude(0.0, 10.0, 1.0);
+        assert_eq!(north.len(), 2);
+        assert_eq!(north[1].lat, 90.0);
+    }
+
+    #[test]
+    fn rhumb_grid_bearing() {
+        let a = Coord::new(-23.0095839, -43.4361816);
+        let b = Coord::new(-22.9, -43.2);
+        let utm: Utm = a.into();
+        let gamma = utm.convergence(&Datum::wgs84());
+        let expected = (a.rhumb_bearing(&b) - gamma).rem_euclid(360.0);
+        assert!((a.rhumb_grid_bearing(&b) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn horizon_distance() {
+        let coord = Coord::new(-23.0, -43.0);
+        let datum = Datum::wgs84();
+        assert_eq!(coord.horizon_distance(2.0, &datum).round(), 5048.0);
+        assert_eq!(coord.horizon_distance(100.0, &datum).round(), 35696.0);
+        assert_eq!(coord.horizon_distance(0.0, &datum), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn horizon_distance_negative_height() {
+        Coord::new(0.0, 0.0).horizon_distance(-1.0, &Datum::wgs84());
+    }
+
+    #[test]
+    fn look_angles() {
+        let here = Coord::new(-22.9519, -43.2105);
+        let (_, elevation) = here.look_angles(&here, 10.0, 1000.0);
+        assert!((elevation - 90.0).abs() < 1e-9);
+        let (_, elevation) = here.look_angles(&here, 1000.0, 10.0);
+        assert!((elevation + 90.0).abs() < 1e-9);
+
+        // A point due east at the same height sits below the local horizon
+        let east = here.destination(90.0, 100000.0);
+        let (azimuth, elevation) = here.look_angles(&east, 0.0, 0.0);
+        assert!((azimuth - 90.0).abs() < 0.5);
+        assert!(elevation < -0.3 && elevation > -0.6);
+
+        let north = Coord::new(-22.8519, -43.2105);
+        let (azimuth, _) = here.look_angles(&north, 0.0, 0.0);
+        assert!(azimuth.abs() < 1e-9);
+    }
+
+    #[test]
+    fn path_by_bearing() {
+        let high = Coord::new(70.0, 0.0);
+        let high_path = high.path_by_bearing(&high.destination(45.0, 2000000.0), 1.0);
+        let low = Coord::new(10.0, 0.0);
+        let low_path = low.path_by_bearing(&low.destination(45.0, 2000000.0), 1.0);
+        assert!(high_path.len() > low_path.len());
+
+        for path in [&high_path, &low_path].iter() {
+            for leg in path.windows(2) {
+                let turn =
+                    math::angle_diff(bearing(&leg[0], &leg[1]), bearing(&leg[1], &leg[0]) + 180.0);
+                assert!(turn.abs() <= 1.0);
+            }
+        }
+
+        let a = Coord::new(0.0, 10.0);
+        let b = Coord::new(0.0, 20.0);
+        let path = a.path_by_bearing(&b, 1.0);
+        assert_eq!(path.len(), 2);
+        assert_eq!(path[1].lon, 20.0);
+    }
+
+    #[test]
+    fn round_trip_through_utm() {
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        let (back, zone, residual) = coord.round_trip_through_utm();
+        assert_eq!(zone, 23);
+        assert!(residual < 1e-3);
+        assert!((back.lat - coord.lat).abs() < 1e-9);
+        assert!((back.lon - coord.lon).abs() < 1e-9);
+
+        let (_, zone, residual) = Coord::new(60.0, 4.0).round_trip_through_utm();
+        assert_eq!(zone, 32);
+        assert!(residual < 1e-3);
+    }
+
+    #[test]
+    fn nearest_grid_intersection() {
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        let utm = coord.nearest_grid_intersection(1000.0);
+        assert_eq!((utm.easting, utm.northing), (660000.0, 7455000.0));
+        assert_eq!((utm.zone, utm.band, utm.north), (23, 'K', false));
+
+        let utm = coord.nearest_grid_intersection(100000.0);
+        assert_eq!((utm.easting, utm.northing), (700000.0, 7500000.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn nearest_grid_intersection_invalid_spacing() {
+        Coord::new(0.0, 0.0).nearest_grid_intersection(0.0);
+    }
+
+    #[test]
+    fn utm_suitability() {
+        assert_eq!(
+            Coord::new(-23.0, -45.0).utm_suitability(),
+            Suitability::Good
+        );
+        assert_eq!(Coord::new(45.0, -44.0).utm_suitability(), Suitability::Good);
+        // Edge of zone 31 on the equator, with a scale error of 0.097%
+        assert_eq!(Coord::new(0.0, 5.99).utm_suitability(), Suitability::Good);
+        // Zone 32V extends 6° west of its central meridian
+        assert_eq!(
+            Coord::new(56.5, 3.1).utm_suitability(),
+            Suitability::Marginal
+        );
+
+        assert_eq!(Suitability::from_scale_factor(0.9996), Suitability::Good);
+        assert_eq!(
+            Suitability::from_scale_factor(1.0015),
+            Suitability::Marginal
+        );
+        assert_eq!(Suitability::from_scale_factor(1.0025), Suitability::Poor);
+        assert_eq!(Suitability::from_scale_factor(0.997), Suitability::Poor);
+    }
+
+    #[test]
+    fn projection_factors() {
+        let (gamma, k) = Coord::new(-23.0095839, -45.0).projection_factors(23);
+        assert!(gamma.abs() < 1e-12);
+        assert!((k - 0.9996).abs() < 1e-12);
+
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        let utm: Utm = coord.into();
+        let (gamma, k) = coord.projection_factors(23);
+        assert!((gamma - utm.convergence(&Datum::wgs84())).abs() < 1e-9);
+        assert!(k > 0.9996);
+    }
+
+    #[test]
+    fn weighted_centroid() {
+        let a = Coord::new(10.0, 20.0);
+        let b = Coord::new(30.0, 40.0);
+
+        let plain = centroid(&[a, b]).unwrap();
+        let equal = super::weighted_centroid(&[(a, 2.0), (b, 2.0)]).unwrap();
+        assert!((plain.lat - equal.lat).abs() < 1e-12);
+        assert!((plain.lon - equal.lon).abs() < 1e-12);
+        assert!((plain.haversine_distance(&a) - plain.haversine_distance(&b)).abs() < 1e-6);
+
+        let biased = super::weighted_centroid(&[(a, 1.0), (b, 3.0)]).unwrap();
+        assert!(biased.haversine_distance(&b) < biased.haversine_distance(&a));
+
+        assert!(super::weighted_centroid(&[]).is_none());
+        assert!(super::weighted_centroid(&[(a, 0.0), (b, 0.0)]).is_none());
+        assert!(centroid(&[Coord::new(0.0, 0.0), Coord::new(0.0, 180.0)]).is_none());
+    }
 }
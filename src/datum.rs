@@ -1,5 +1,100 @@
+use crate::coord::Coord;
+use crate::ecef::Ecef;
 use crate::math;
 
+use std::sync::OnceLock;
+
+use thiserror::Error;
+
+/// Defining and derived parameters of the WGS84 ellipsoid
+pub mod wgs84_constants {
+    /// Semi-major axis, in meters
+    pub const SEMI_MAJOR_AXIS: f64 = 6378137.0;
+    /// Inverse flattening
+    pub const INVERSE_FLATTENING: f64 = 298.257223563;
+    /// Flattening
+    pub const FLATTENING: f64 = 1.0 / INVERSE_FLATTENING;
+    /// First eccentricity squared
+    pub const ECCENTRICITY_SQUARED: f64 = FLATTENING * (2.0 - FLATTENING);
+    /// Mean radius `(2a + b) / 3`, in meters
+    pub const MEAN_RADIUS: f64 = SEMI_MAJOR_AXIS * (3.0 - FLATTENING) / 3.0;
+}
+
+/// GRS80 semi-major axis and flattening
+pub(crate) const GRS80_ELLIPSOID: (f64, f64) = (6378137.0, 1.0 / 298.257222101);
+
+/// Krüger series coefficients for the forward projection, in the third flattening
+const ALPCOEFF: [f64; 27] = [
+    31564.0,
+    -66675.0,
+    34440.0,
+    47250.0,
+    -100800.0,
+    75600.0,
+    151200.0,
+    -1983433.0,
+    863232.0,
+    748608.0,
+    -1161216.0,
+    524160.0,
+    1935360.0,
+    670412.0,
+    406647.0,
+    -533952.0,
+    184464.0,
+    725760.0,
+    6601661.0,
+    -7732800.0,
+    2230245.0,
+    7257600.0,
+    -13675556.0,
+    3438171.0,
+    7983360.0,
+    212378941.0,
+    319334400.0,
+];
+
+/// Krüger series coefficients for the inverse projection, in the third flattening
+const BETCOEFF: [f64; 27] = [
+    384796.0,
+    -382725.0,
+    -6720.0,
+    932400.0,
+    -1612800.0,
+    1209600.0,
+    2419200.0,
+    -1118711.0,
+    1695744.0,
+    -1174656.0,
+    258048.0,
+    80640.0,
+    3870720.0,
+    22276.0,
+    -16929.0,
+    -15984.0,
+    12852.0,
+    362880.0,
+    -830251.0,
+    -158400.0,
+    197865.0,
+    7257600.0,
+    -435388.0,
+    453717.0,
+    15966720.0,
+    20648693.0,
+    638668800.0,
+];
+
+/// Coefficients of the rectifying radius series, in the third flattening
+const B1COEFF: [f64; 5] = [1.0, 4.0, 64.0, 256.0, 256.0];
+
+/// Errors produced when building a Datum
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum DatumError {
+    #[error("Unknown EPSG ellipsoid code: {0}")]
+    UnknownEpsg(u32),
+}
+
 ///
 /// Holds conventional datum information
 ///
@@ -12,6 +107,7 @@ use crate::math;
 /// }
 /// ```
 ///
+#[derive(Debug, Clone, PartialEq)]
 pub struct Datum {
     pub a: f64,
     pub f: f64,
@@ -41,12 +137,11 @@ impl Datum {
         b1coeff: &[f64],
     ) -> Datum {
         let e2: f64 = f * (2.0 - f);
-        let es: f64;
-        if f <= 0.0 {
-            es = -e2.abs().sqrt();
+        let es: f64 = if f <= 0.0 {
+            -e2.abs().sqrt()
         } else {
-            es = e2.abs().sqrt();
-        }
+            e2.abs().sqrt()
+        };
         let e2m: f64 = 1.0 - e2;
         let c: f64 = e2m.sqrt() * math::eatanhe(1.0, es).exp();
         let n: f64 = f / (2.0 - f);
@@ -71,8 +166,8 @@ impl Datum {
             let m = maxpow - i - 1;
             alp.push(d * math::polyval(m, &alpcoeff[o..], n) / alpcoeff[o + m + 1]);
             bet.push(d * math::polyval(m, &betcoeff[o..], n) / betcoeff[o + m + 1]);
-            o = o + m + 2;
-            d = d * n;
+            o += m + 2;
+            d *= n;
         }
 
         Datum {
@@ -94,73 +189,270 @@ impl Datum {
         }
     }
 
+    /// Semi-major axis of the ellipsoid, in meters.
+    pub fn semi_major_axis(&self) -> f64 {
+        self.a
+    }
+
+    /// Flattening of the ellipsoid.
+    pub fn flattening(&self) -> f64 {
+        self.f
+    }
+
+    /// First eccentricity squared of the ellipsoid.
+    pub fn eccentricity_squared(&self) -> f64 {
+        self.e2
+    }
+
+    /// Central scale factor of the projection.
+    pub fn scale_factor(&self) -> f64 {
+        self.k0
+    }
+
+    /// Mean radius `(2a + b) / 3` of the ellipsoid, in meters.
+    pub fn mean_radius(&self) -> f64 {
+        self.a * (3.0 - self.f) / 3.0
+    }
+
+    /// Distance, in meters, along the meridian from the equator to `lat_deg`.
+    ///
+    /// Evaluated with Helmert's series in the third flattening, which is
+    /// accurate to well below a millimeter. Southern latitudes give negative
+    /// distances.
+    pub fn meridian_arc(&self, lat_deg: f64) -> f64 {
+        let n = self.n;
+        let n2 = n.powi(2);
+        let phi = lat_deg.to_radians();
+
+        self.a / (1.0 + n)
+            * ((1.0 + n2 / 4.0 + n2.powi(2) / 64.0) * phi
+                - 1.5 * (n - n.powi(3) / 8.0) * (2.0 * phi).sin()
+                + 15.0 / 16.0 * (n2 - n2.powi(2) / 4.0) * (4.0 * phi).sin()
+                - 35.0 / 48.0 * n.powi(3) * (6.0 * phi).sin()
+                + 315.0 / 512.0 * n2.powi(2) * (8.0 * phi).sin())
+    }
+
     /// Return a new datum WGS84 instance.
     pub fn wgs84() -> Datum {
-        Datum::new(
-            6378137.0,
-            0.0033528106647474805,
-            0.99960000000000004,
-            &[
-                31564.0,
-                -66675.0,
-                34440.0,
-                47250.0,
-                -100800.0,
-                75600.0,
-                151200.0,
-                -1983433.0,
-                863232.0,
-                748608.0,
-                -1161216.0,
-                524160.0,
-                1935360.0,
-                670412.0,
-                406647.0,
-                -533952.0,
-                184464.0,
-                725760.0,
-                6601661.0,
-                -7732800.0,
-                2230245.0,
-                7257600.0,
-                -13675556.0,
-                3438171.0,
-                7983360.0,
-                212378941.0,
-                319334400.0,
-            ],
-            &[
-                384796.0,
-                -382725.0,
-                -6720.0,
-                932400.0,
-                -1612800.0,
-                1209600.0,
-                2419200.0,
-                -1118711.0,
-                1695744.0,
-                -1174656.0,
-                258048.0,
-                80640.0,
-                3870720.0,
-                22276.0,
-                -16929.0,
-                -15984.0,
-                12852.0,
-                362880.0,
-                -830251.0,
-                -158400.0,
-                197865.0,
-                7257600.0,
-                -435388.0,
-                453717.0,
-                15966720.0,
-                20648693.0,
-                638668800.0,
-            ],
-            &[1.0, 4.0, 64.0, 256.0, 256.0],
+        Datum::with_ellipsoid(
+            wgs84_constants::SEMI_MAJOR_AXIS,
+            wgs84_constants::FLATTENING,
         )
     }
+
+    /// Shared WGS84 instance, built once on first use.
+    ///
+    /// Building a datum evaluates the Krüger series coefficients, so code
+    /// converting many points should borrow this rather than calling
+    /// [`Datum::wgs84`] for each of them.
+    pub fn wgs84_ref() -> &'static Datum {
+        static WGS84: OnceLock<Datum> = OnceLock::new();
+        WGS84.get_or_init(Datum::wgs84)
+    }
+
+    /// Return a new datum GRS80 instance.
+    pub fn grs80() -> Datum {
+        let (a, f) = GRS80_ELLIPSOID;
+        Datum::with_ellipsoid(a, f)
+    }
+
+    /// Datum for an ellipsoid identified by its EPSG code, with the UTM scale
+    /// factor 0.9996.
+    ///
+    /// Supported codes are 7030 (WGS84), 7019 (GRS80), 7008 (Clarke 1866),
+    /// 7001 (Airy 1830), 7004 (Bessel 1841) and 7022 (International 1924).
+    ///
+    /// # Example
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let airy = datum::Datum::from_epsg_ellipsoid(7001).unwrap();
+    /// assert_eq!(airy.a, 6377563.396);
+    /// ```
+    pub fn from_epsg_ellipsoid(code: u32) -> Result<Datum, DatumError> {
+        let (a, f) = match code {
+            7030 => (
+                wgs84_constants::SEMI_MAJOR_AXIS,
+                wgs84_constants::FLATTENING,
+            ),
+            7019 => GRS80_ELLIPSOID,
+            7008 => DatumName::Nad27.ellipsoid(),
+            7001 => DatumName::Osgb36.ellipsoid(),
+            7004 => DatumName::Tokyo.ellipsoid(),
+            7022 => DatumName::Ed50.ellipsoid(),
+            _ => return Err(DatumError::UnknownEpsg(code)),
+        };
+
+        Ok(Datum::with_ellipsoid(a, f))
+    }
+
+    /// Datum with the UTM scale factor over the given ellipsoid
+    fn with_ellipsoid(a: f64, f: f64) -> Datum {
+        Datum::transverse_mercator(a, f, 0.9996)
+    }
+
+    /// Datum over the given ellipsoid with a custom central scale factor
+    pub(crate) fn transverse_mercator(a: f64, f: f64, k0: f64) -> Datum {
+        Datum::new(a, f, k0, &ALPCOEFF, &BETCOEFF, &B1COEFF)
+    }
+}
+
+///
+/// Seven parameter Helmert transformation between geocentric frames
+///
+/// Uses the position vector convention. Translations are in meters,
+/// rotations in arc-seconds and the scale change in parts per million.
+///
+/// # Example
+/// ```
+/// use geomorph::*;
+///
+/// let params = datum::DatumName::Osgb36.helmert_from_wgs84();
+/// let coord = coord::Coord::new(51.4778, -0.0015);
+/// let shifted = params.transform(
+///     &coord,
+///     datum::DatumName::WGS84_ELLIPSOID,
+///     datum::DatumName::Osgb36.ellipsoid(),
+/// );
+/// ```
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HelmertParams {
+    pub tx: f64,
+    pub ty: f64,
+    pub tz: f64,
+    pub rx: f64,
+    pub ry: f64,
+    pub rz: f64,
+    pub s: f64,
+}
+
+impl HelmertParams {
+    /// Transform a coordinate between two ellipsoids, given as
+    /// `(semi-major axis, flattening)` pairs.
+    ///
+    /// The point is assumed to lie on the source ellipsoid surface and the
+    /// height on the target ellipsoid is discarded.
+    pub fn transform(&self, coord: &Coord, source: (f64, f64), target: (f64, f64)) -> Coord {
+        let [x, y, z] = to_geocentric(coord, 0.0, source);
+
+        let arcsec = (1.0_f64 / 3600.0).to_radians();
+        let (rx, ry, rz) = (self.rx * arcsec, self.ry * arcsec, self.rz * arcsec);
+        let s = 1.0 + self.s * 1e-6;
+
+        let x2 = self.tx + s * x - rz * y + ry * z;
+        let y2 = self.ty + rz * x + s * y - rx * z;
+        let z2 = self.tz - ry * x + rx * y + s * z;
+
+        let (a, f) = target;
+        let (coord, _) = Ecef::new(x2, y2, z2).to_coord(&Datum::with_ellipsoid(a, f));
+        coord
+    }
+}
+
+/// Datums with built-in shift parameters from WGS84
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatumName {
+    /// North American Datum 1927, Clarke 1866 ellipsoid
+    Nad27,
+    /// Ordnance Survey Great Britain 1936, Airy 1830 ellipsoid
+    Osgb36,
+    /// European Datum 1950, International 1924 ellipsoid
+    Ed50,
+    /// Tokyo datum, Bessel 1841 ellipsoid
+    Tokyo,
+}
+
+impl DatumName {
+    /// WGS84 semi-major axis and flattening
+    pub const WGS84_ELLIPSOID: (f64, f64) = (
+        wgs84_constants::SEMI_MAJOR_AXIS,
+        wgs84_constants::FLATTENING,
+    );
+
+    /// Semi-major axis, in meters, and flattening of the datum ellipsoid.
+    pub fn ellipsoid(self) -> (f64, f64) {
+        match self {
+            DatumName::Nad27 => (6378206.4, 1.0 / 294.978_698_2),
+            DatumName::Osgb36 => (6377563.396, 1.0 / 299.324_964_6),
+            DatumName::Ed50 => (6378388.0, 1.0 / 297.0),
+            DatumName::Tokyo => (6377397.155, 1.0 / 299.152_812_8),
+        }
+    }
+
+    /// Published Helmert parameters transforming WGS84 into this datum.
+    pub fn helmert_from_wgs84(self) -> HelmertParams {
+        match self {
+            DatumName::Nad27 => HelmertParams {
+                tx: 8.0,
+                ty: -160.0,
+                tz: -176.0,
+                rx: 0.0,
+                ry: 0.0,
+                rz: 0.0,
+                s: 0.0,
+            },
+            DatumName::Osgb36 => HelmertParams {
+                tx: -446.448,
+                ty: 125.157,
+                tz: -542.060,
+                rx: -0.1502,
+                ry: -0.2470,
+                rz: -0.8421,
+                s: 20.4894,
+            },
+            DatumName::Ed50 => HelmertParams {
+                tx: 89.5,
+                ty: 93.8,
+                tz: 123.1,
+                rx: 0.0,
+                ry: 0.0,
+                rz: 0.156,
+                s: -1.2,
+            },
+            DatumName::Tokyo => HelmertParams {
+                tx: 148.0,
+                ty: -507.0,
+                tz: -685.0,
+                rx: 0.0,
+                ry: 0.0,
+                rz: 0.0,
+                s: 0.0,
+            },
+        }
+    }
+}
+
+///
+/// Shift a WGS84 coordinate into one of the built-in datums
+///
+/// # Example
+/// ```
+/// use geomorph::*;
+///
+/// let wgs84 = coord::Coord::new(51.4778, -0.0015);
+/// let osgb36 = datum::shift_wgs84_to(&wgs84, datum::DatumName::Osgb36);
+/// ```
+///
+pub fn shift_wgs84_to(coord: &Coord, target: DatumName) -> Coord {
+    target
+        .helmert_from_wgs84()
+        .transform(coord, DatumName::WGS84_ELLIPSOID, target.ellipsoid())
+}
+
+/// Geocentric cartesian coordinates of a point `height` meters above the
+/// ellipsoid surface
+pub(crate) fn to_geocentric(coord: &Coord, height: f64, (a, f): (f64, f64)) -> [f64; 3] {
+    let e2 = f * (2.0 - f);
+    let (sinphi, cosphi) = coord.lat.to_radians().sin_cos();
+    let (sinlam, coslam) = coord.lon.to_radians().sin_cos();
+    let nu = a / (1.0 - e2 * sinphi.powi(2)).sqrt();
+
+    [
+        (nu + height) * cosphi * coslam,
+        (nu + height) * cosphi * sinlam,
+        (nu * (1.0 - e2) + height) * sinphi,
+    ]
 }
 
 #[cfg(test)]
@@ -172,4 +464,116 @@ mod tests {
         let a: Datum = Datum::wgs84();
         assert_eq!((a.n * 100000000.0).trunc(), 167922.0);
     }
+
+    #[test]
+    fn wgs84_ref() {
+        let shared = Datum::wgs84_ref();
+        assert_eq!(shared, &Datum::wgs84());
+        assert!(std::ptr::eq(shared, Datum::wgs84_ref()));
+    }
+
+    #[test]
+    fn instantiate_grs80() {
+        let a: Datum = Datum::grs80();
+        assert_eq!((a.n * 100000000.0).trunc(), 167922.0);
+        assert_eq!((a.n * 1e16).round(), 16792203946287.0);
+        assert_eq!(a.a, Datum::wgs84().a);
+        assert!(a.f > Datum::wgs84().f);
+    }
+
+    #[test]
+    fn meridian_arc() {
+        let a: Datum = Datum::wgs84();
+        assert_eq!(a.meridian_arc(0.0), 0.0);
+        assert_eq!((a.meridian_arc(45.0) * 1000.0).round(), 4984944378.0);
+        assert_eq!((a.meridian_arc(90.0) * 1000.0).round(), 10001965729.0);
+        assert_eq!(a.meridian_arc(-45.0), -a.meridian_arc(45.0));
+    }
+
+    #[test]
+    fn mean_radius() {
+        let a: Datum = Datum::wgs84();
+        assert_eq!(a.mean_radius().round(), 6371009.0);
+        assert_eq!(a.mean_radius(), wgs84_constants::MEAN_RADIUS);
+    }
+
+    #[test]
+    fn wgs84_constants() {
+        let a: Datum = Datum::wgs84();
+        assert_eq!(a.a, wgs84_constants::SEMI_MAJOR_AXIS);
+        assert_eq!(a.f, wgs84_constants::FLATTENING);
+        assert!((a.es.powi(2) - wgs84_constants::ECCENTRICITY_SQUARED).abs() < 1e-18);
+    }
+
+    #[test]
+    fn getters() {
+        let a: Datum = Datum::wgs84();
+        assert_eq!(a.semi_major_axis(), 6378137.0);
+        assert_eq!(a.flattening(), 1.0 / 298.257223563);
+        assert!((a.eccentricity_squared() - 0.00669437999014).abs() < 1e-14);
+        assert_eq!(a.scale_factor(), 0.9996);
+    }
+
+    #[test]
+    fn shift_wgs84_to() {
+        // Caister water tower, from the Ordnance Survey coordinate guide. The
+        // reference OSGB36 position comes from OSTN, which the seven parameter
+        // transformation approximates to a few meters.
+        let dms = |d: f64, m: f64, s: f64| d + m / 60.0 + s / 3600.0;
+        let wgs84 = Coord::new(dms(52.0, 39.0, 28.8282), dms(1.0, 42.0, 57.8663));
+        let osgb36 = super::shift_wgs84_to(&wgs84, DatumName::Osgb36);
+
+        let (lat, lon) = (dms(52.0, 39.0, 27.2531), dms(1.0, 43.0, 4.5177));
+        let dy = (osgb36.lat - lat).to_radians() * wgs84_constants::MEAN_RADIUS;
+        let dx =
+            (osgb36.lon - lon).to_radians() * wgs84_constants::MEAN_RADIUS * lat.to_radians().cos();
+        assert!(dx.hypot(dy) < 5.0);
+        assert!(wgs84.haversine_distance(&osgb36) > 100.0);
+    }
+
+    #[test]
+    fn helmert_identity() {
+        let params = HelmertParams {
+            tx: 0.0,
+            ty: 0.0,
+            tz: 0.0,
+            rx: 0.0,
+            ry: 0.0,
+            rz: 0.0,
+            s: 0.0,
+        };
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        let same = params.transform(
+            &coord,
+            DatumName::WGS84_ELLIPSOID,
+            DatumName::WGS84_ELLIPSOID,
+        );
+        assert!((same.lat - coord.lat).abs() < 1e-10);
+        assert!((same.lon - coord.lon).abs() < 1e-10);
+    }
+
+    #[test]
+    fn from_epsg_ellipsoid() {
+        assert_eq!(Datum::from_epsg_ellipsoid(7030).unwrap(), Datum::wgs84());
+
+        let grs80 = Datum::from_epsg_ellipsoid(7019).unwrap();
+        assert_eq!(grs80.a, 6378137.0);
+        assert!((grs80.f - Datum::wgs84().f).abs() < 1e-10);
+
+        for &(code, a) in [
+            (7008, 6378206.4),
+            (7001, 6377563.396),
+            (7004, 6377397.155),
+            (7022, 6378388.0),
+        ]
+        .iter()
+        {
+            assert_eq!(Datum::from_epsg_ellipsoid(code).unwrap().a, a);
+        }
+
+        assert_eq!(
+            Datum::from_epsg_ellipsoid(4326).unwrap_err(),
+            DatumError::UnknownEpsg(4326)
+        );
+    }
 }
@@ -0,0 +1,165 @@
+use crate::coord::{Coord, CoordAlt};
+use crate::datum::{self, Datum};
+
+use std::fmt;
+
+///
+/// Earth-centered, earth-fixed cartesian coordinates, in meters
+///
+/// The origin is the center of the ellipsoid, the z axis points to the north
+/// pole and the x axis crosses the equator at the prime meridian.
+///
+/// # Example
+/// ```
+/// use geomorph::*;
+///
+/// let datum = datum::Datum::wgs84();
+/// let coord = coord::Coord::new(-23.0095839, -43.4361816);
+/// let ecef = ecef::Ecef::from_coord(&coord, 10.0, &datum);
+/// let (back, altitude) = ecef.to_coord(&datum);
+/// assert!((back.lat - coord.lat).abs() < 1e-9);
+/// assert!((altitude - 10.0).abs() < 1e-3);
+/// ```
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ecef {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Ecef {
+    /// Return a new Ecef instance.
+    pub fn new(x: f64, y: f64, z: f64) -> Ecef {
+        Ecef { x, y, z }
+    }
+
+    /// Cartesian position of a point `altitude_m` meters above the
+    /// ellipsoid of `datum`.
+    pub fn from_coord(coord: &Coord, altitude_m: f64, datum: &Datum) -> Ecef {
+        let [x, y, z] = datum::to_geocentric(coord, altitude_m, (datum.a, datum.f));
+        Ecef { x, y, z }
+    }
+
+    /// Cartesian position of a point with altitude over the ellipsoid of
+    /// `datum`.
+    pub fn from_coord_alt(coord: &CoordAlt, datum: &Datum) -> Ecef {
+        Ecef::from_coord(&coord.coord, coord.altitude, datum)
+    }
+
+    /// Geodetic coordinates and altitude above the ellipsoid of `datum`.
+    ///
+    /// Uses Bowring's closed form for the latitude, accurate to well below a
+    /// millimeter for points near the earth surface. The longitude is 0.0 on
+    /// the polar axis.
+    pub fn to_coord(&self, datum: &Datum) -> (Coord, f64) {
+        let a = datum.a;
+        let b = a * (1.0 - datum.f);
+        let ep2 = datum.e2 / (1.0 - datum.e2);
+        let p = self.x.hypot(self.y);
+
+        let (sintheta, costheta) = (self.z * a).atan2(p * b).sin_cos();
+        let phi = (self.z + ep2 * b * sintheta.powi(3)).atan2(p - datum.e2 * a * costheta.powi(3));
+        let (sinphi, cosphi) = phi.sin_cos();
+
+        // Valid at any latitude, including the poles where p / cos(phi)
+        // is undefined
+        let altitude = p * cosphi + self.z * sinphi - a * (1.0 - datum.e2 * sinphi.powi(2)).sqrt();
+
+        let lon = if p == 0.0 {
+            0.0
+        } else {
+            self.y.atan2(self.x).to_degrees()
+        };
+
+        (Coord::new(phi.to_degrees(), lon), altitude)
+    }
+
+    /// Geodetic coordinates with the altitude above the ellipsoid of
+    /// `datum`.
+    ///
+    /// Equivalent to [`Ecef::to_coord`].
+    pub fn to_coord_alt(&self, datum: &Datum) -> CoordAlt {
+        let (coord, altitude) = self.to_coord(datum);
+        CoordAlt::new(coord, altitude)
+    }
+}
+
+impl fmt::Display for Ecef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({:.3}, {:.3}, {:.3})", self.x, self.y, self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_coord() {
+        let datum = Datum::wgs84();
+
+        let ecef = Ecef::from_coord(&Coord::new(0.0, 0.0), 0.0, &datum);
+        assert_eq!(ecef, Ecef::new(datum.a, 0.0, 0.0));
+
+        let ecef = Ecef::from_coord(&Coord::new(90.0, 0.0), 0.0, &datum);
+        assert!(ecef.x.abs() < 1e-9);
+        assert!((ecef.z - datum.a * (1.0 - datum.f)).abs() < 1e-6);
+
+        let ecef = Ecef::from_coord(&Coord::new(0.0, 90.0), 1000.0, &datum);
+        assert!((ecef.y - datum.a - 1000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn round_trip() {
+        let datum = Datum::wgs84();
+        let coords = [
+            Coord::new(0.0, 0.0),
+            Coord::new(-23.0095839, -43.4361816),
+            Coord::new(45.0, 179.5),
+            Coord::new(-60.0, -120.0),
+            Coord::new(89.9999, 45.0),
+            Coord::new(-89.999, -10.0),
+        ];
+
+        for coord in coords.iter() {
+            for &altitude in [0.0, 10000.0].iter() {
+                let ecef = Ecef::from_coord(coord, altitude, &datum);
+                let (back, h) = ecef.to_coord(&datum);
+                assert!((back.lat - coord.lat).abs() < 1e-9, "{} {}", coord, back);
+                assert!((back.lon - coord.lon).abs() < 1e-9, "{} {}", coord, back);
+                assert!((h - altitude).abs() < 1e-3, "{} {}", coord, h);
+            }
+        }
+    }
+
+    #[test]
+    fn coord_alt_round_trip() {
+        let datum = Datum::wgs84();
+        let coord = CoordAlt::new(Coord::new(51.4778, -0.0015), 8848.86);
+        let back = Ecef::from_coord_alt(&coord, &datum).to_coord_alt(&datum);
+        assert!((back.altitude() - 8848.86).abs() < 1e-3);
+        assert!((back.coord().lat - 51.4778).abs() < 1e-9);
+        assert!((back.coord().lon + 0.0015).abs() < 1e-9);
+
+        let surface: CoordAlt = Coord::new(-23.0095839, -43.4361816).into();
+        assert_eq!(surface.altitude(), 0.0);
+        let back = Ecef::from_coord_alt(&surface, &datum).to_coord_alt(&datum);
+        assert!(back.altitude().abs() < 1e-3);
+    }
+
+    #[test]
+    fn to_coord_at_poles() {
+        let datum = Datum::wgs84();
+        let b = datum.a * (1.0 - datum.f);
+
+        let (coord, h) = Ecef::new(0.0, 0.0, b + 10000.0).to_coord(&datum);
+        assert_eq!((coord.lat, coord.lon), (90.0, 0.0));
+        assert!((h - 10000.0).abs() < 1e-6);
+
+        let (coord, h) = Ecef::new(0.0, 0.0, -b).to_coord(&datum);
+        assert_eq!((coord.lat, coord.lon), (-90.0, 0.0));
+        assert!(h.abs() < 1e-6);
+    }
+}
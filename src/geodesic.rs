@@ -0,0 +1,792 @@
+//! Inverse geodesic problem on the ellipsoid, following C. F. F. Karney,
+//! "Algorithms for geodesics", J. Geodesy 87, 43-55 (2013), and the
+//! GeographicLib implementation with series expansions of order 6.
+
+use crate::datum::Datum;
+use crate::math;
+
+use std::f64::consts::PI;
+
+const ORDER: usize = 6;
+const TOL0: f64 = f64::EPSILON;
+const TOL1: f64 = 200.0 * TOL0;
+const MAXIT1: usize = 20;
+const MAXIT2: usize = MAXIT1 + f64::MANTISSA_DIGITS as usize + 10;
+
+/// Coefficients of the A3 and C3 series, which only depend on the
+/// ellipsoid
+struct Geodesic {
+    a: f64,
+    f: f64,
+    f1: f64,
+    ep2: f64,
+    n: f64,
+    b: f64,
+    etol2: f64,
+    a3x: [f64; ORDER],
+    c3x: [f64; 15],
+}
+
+/// Intermediate values of the geodesic computed by [`Geodesic::lambda12`]
+#[derive(Default)]
+struct Lambda {
+    lam12: f64,
+    salp2: f64,
+    calp2: f64,
+    sig12: f64,
+    ssig1: f64,
+    csig1: f64,
+    ssig2: f64,
+    csig2: f64,
+    eps: f64,
+    dlam12: f64,
+}
+
+/// Starting point for Newton's method, or the solution of a short line
+struct Start {
+    sig12: f64,
+    salp1: f64,
+    calp1: f64,
+    salp2: f64,
+    calp2: f64,
+    dnm: f64,
+}
+
+///
+/// Solve the inverse geodesic problem between two points
+///
+/// Returns the length of the geodesic in meters and the azimuths at both
+/// ends, in degrees clockwise from north in the interval [-180..180]. The
+/// solution is accurate to a few nanometers, including for nearly antipodal
+/// points.
+///
+pub(crate) fn inverse(
+    datum: &Datum,
+    lat1: f64,
+    lon1: f64,
+    lat2: f64,
+    lon2: f64,
+) -> (f64, f64, f64) {
+    Geodesic::new(datum.a, datum.f).inverse(lat1, lon1, lat2, lon2)
+}
+
+impl Geodesic {
+    fn new(a: f64, f: f64) -> Geodesic {
+        let f1 = 1.0 - f;
+        let e2 = f * (2.0 - f);
+        let tol2 = TOL0.sqrt();
+
+        let mut geodesic = Geodesic {
+            a,
+            f,
+            f1,
+            ep2: e2 / f1.powi(2),
+            n: f / (2.0 - f),
+            b: a * f1,
+            etol2: 0.1 * tol2 / (f.abs().max(0.001) * (1.0 - f / 2.0).min(1.0) / 2.0).sqrt(),
+            a3x: [0.0; ORDER],
+            c3x: [0.0; 15],
+        };
+        geodesic.a3coeff();
+        geodesic.c3coeff();
+
+        geodesic
+    }
+
+    fn a3coeff(&mut self) {
+        const COEFF: [f64; 18] = [
+            -3.0, 128.0, // eps^5
+            -2.0, -3.0, 64.0, // eps^4
+            -1.0, -3.0, -1.0, 16.0, // eps^3
+            3.0, -1.0, -2.0, 8.0, // eps^2
+            1.0, -1.0, 2.0, // eps^1
+            1.0, 1.0, // eps^0
+        ];
+
+        let mut o = 0;
+        for (k, j) in (0..ORDER).rev().enumerate() {
+            let m = (ORDER - j - 1).min(j);
+            self.a3x[k] = math::polyval(m, &COEFF[o..], self.n) / COEFF[o + m + 1];
+            o += m + 2;
+        }
+    }
+
+    fn c3coeff(&mut self) {
+        const COEFF: [f64; 45] = [
+            3.0, 128.0, // C3[1], eps^5
+            2.0, 5.0, 128.0, // C3[1], eps^4
+            -1.0, 3.0, 3.0, 64.0, // C3[1], eps^3
+            -1.0, 0.0, 1.0, 8.0, // C3[1], eps^2
+            -1.0, 1.0, 4.0, // C3[1], eps^1
+            5.0, 256.0, // C3[2], eps^5
+            1.0, 3.0, 128.0, // C3[2], eps^4
+            -3.0, -2.0, 3.0, 64.0, // C3[2], eps^3
+            1.0, -3.0, 2.0, 32.0, // C3[2], eps^2
+            7.0, 512.0, // C3[3], eps^5
+            -10.0, 9.0, 384.0, // C3[3], eps^4
+            5.0, -9.0, 5.0, 192.0, // C3[3], eps^3
+            7.0, 512.0, // C3[4], eps^5
+            -14.0, 7.0, 512.0, // C3[4], eps^4
+            21.0, 2560.0, // C3[5], eps^5
+        ];
+
+        let (mut o, mut k) = (0, 0);
+        for l in 1..ORDER {
+            for j in (l..ORDER).rev() {
+                let m = (ORDER - j - 1).min(j);
+                self.c3x[k] = math::polyval(m, &COEFF[o..], self.n) / COEFF[o + m + 1];
+                k += 1;
+                o += m + 2;
+            }
+        }
+    }
+
+    fn a3f(&self, eps: f64) -> f64 {
+        math::polyval(ORDER - 1, &self.a3x, eps)
+    }
+
+    fn c3f(&self, eps: f64, c: &mut [f64; ORDER + 1]) {
+        let (mut mult, mut o) = (1.0, 0);
+        for (l, item) in c.iter_mut().enumerate().take(ORDER).skip(1) {
+            let m = ORDER - l - 1;
+            mult *= eps;
+            *item = mult * math::polyval(m, &self.c3x[o..], eps);
+            o += m + 1;
+        }
+    }
+
+    fn inverse(&self, lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> (f64, f64, f64) {
+        // Bring the points to the canonical configuration
+        //   0 <= lon12 <= 180, -90 <= lat1 <= -0, lat1 <= lat2 <= -lat1
+        // keeping track of the changes in lonsign, swapp and latsign
+        let lon12 = math::angle_diff(lon1, lon2);
+        let mut lonsign = if lon12.is_sign_negative() { -1.0 } else { 1.0 };
+        let lon12 = ang_round(lon12 * lonsign);
+        let lam12 = lon12.to_radians();
+        let (slam12, clam12) = sincosd(lon12);
+        let lon12s = 180.0 - lon12;
+
+        let (mut lat1, mut lat2) = (ang_round(lat1), ang_round(lat2));
+        let swapp = if lat1.abs() < lat2.abs() || lat2.is_nan() {
+            -1.0
+        } else {
+            1.0
+        };
+        if swapp < 0.0 {
+            lonsign = -lonsign;
+            std::mem::swap(&mut lat1, &mut lat2);
+        }
+        let latsign = if lat1.is_sign_negative() { 1.0 } else { -1.0 };
+        lat1 *= latsign;
+        lat2 *= latsign;
+
+        let (sbet1, cbet1) = self.reduced_latitude(lat1);
+        let (mut sbet2, mut cbet2) = self.reduced_latitude(lat2);
+
+        // Force bet2 = +/- bet1 exactly when the differences vanish
+        if cbet1 < -sbet1 {
+            if cbet2 == cbet1 {
+                sbet2 = sbet1.copysign(sbet2);
+            }
+        } else if sbet2.abs() == -sbet1 {
+            cbet2 = cbet1;
+        }
+
+        let dn1 = (1.0 + self.ep2 * sbet1.powi(2)).sqrt();
+        let dn2 = (1.0 + self.ep2 * sbet2.powi(2)).sqrt();
+
+        let (mut salp1, mut calp1, mut salp2, mut calp2) = (0.0, 0.0, 0.0, 0.0);
+        let mut s12x = 0.0;
+
+        let mut meridian = lat1 == -90.0 || slam12 == 0.0;
+        if meridian {
+            // The endpoints are on a single full meridian, so the geodesic
+            // might lie on it
+            calp1 = clam12;
+            salp1 = slam12;
+            calp2 = 1.0;
+            salp2 = 0.0;
+
+            // tan(bet) = tan(sig) * cos(alp)
+            let (ssig1, csig1) = (sbet1, calp1 * cbet1);
+            let (ssig2, csig2) = (sbet2, calp2 * cbet2);
+
+            let sig12 = ((csig1 * ssig2 - ssig1 * csig2).max(0.0) + 0.0)
+                .atan2(csig1 * csig2 + ssig1 * ssig2);
+            let (s12b, m12b, _) = self.lengths(self.n, sig12, ssig1, csig1, dn1, ssig2, csig2, dn2);
+
+            // A meridian with sig12 > pi / 2 is not the shortest path on a
+            // prolate ellipsoid
+            if sig12 < 1.0 || m12b >= 0.0 {
+                // Prevent a negative length for zero length lines
+                let tiny = f64::MIN_POSITIVE.sqrt();
+                s12x = if sig12 < 3.0 * tiny || (sig12 < TOL0 && (s12b < 0.0 || m12b < 0.0)) {
+                    0.0
+                } else {
+                    s12b * self.b
+                };
+            } else {
+                meridian = false;
+            }
+        }
+
+        if !meridian && sbet1 == 0.0 && (self.f <= 0.0 || lon12s >= self.f * 180.0) {
+            // The geodesic runs along the equator
+            calp1 = 0.0;
+            calp2 = 0.0;
+            salp1 = 1.0;
+            salp2 = 1.0;
+            s12x = self.a * lam12;
+        } else if !meridian {
+            let start =
+                self.inverse_start(sbet1, cbet1, dn1, sbet2, cbet2, dn2, lam12, slam12, clam12);
+            salp1 = start.salp1;
+            calp1 = start.calp1;
+
+            if start.sig12 >= 0.0 {
+                // Short line, solved by the starting point
+                salp2 = start.salp2;
+                calp2 = start.calp2;
+                s12x = start.sig12 * self.b * start.dnm;
+            } else {
+                // Newton's method on lambda12(alp1) - lam12 = 0, keeping a
+                // bracket (alp1a, alp1b) of the root and bisecting it
+                // whenever a Newton step is not usable
+                let tiny = f64::MIN_POSITIVE.sqrt();
+                let tolb = TOL0 * TOL0.sqrt();
+                let (mut salp1a, mut calp1a, mut salp1b, mut calp1b) = (tiny, 1.0, tiny, -1.0);
+                let (mut tripn, mut tripb) = (false, false);
+                let mut lambda;
+                let mut numit = 0;
+                loop {
+                    lambda = self.lambda12(
+                        sbet1,
+                        cbet1,
+                        dn1,
+                        sbet2,
+                        cbet2,
+                        dn2,
+                        salp1,
+                        calp1,
+                        slam12,
+                        clam12,
+                        numit < MAXIT1,
+                    );
+                    let v = lambda.lam12;
+                    let dv = lambda.dlam12;
+
+                    // Also escape with NaNs
+                    let tol = if tripn { 8.0 } else { 1.0 } * TOL0;
+                    if tripb || v.abs() < tol || v.is_nan() || numit == MAXIT2 {
+                        break;
+                    }
+
+                    if v > 0.0 && (numit > MAXIT1 || calp1 / salp1 > calp1b / salp1b) {
+                        salp1b = salp1;
+                        calp1b = calp1;
+                    } else if v < 0.0 && (numit > MAXIT1 || calp1 / salp1 < calp1a / salp1a) {
+                        salp1a = salp1;
+                        calp1a = calp1;
+                    }
+
+                    numit += 1;
+                    if numit <= MAXIT1 && dv > 0.0 {
+                        let dalp1 = -v / dv;
+                        if dalp1.abs() < PI {
+                            let (sdalp1, cdalp1) = dalp1.sin_cos();
+                            let nsalp1 = salp1 * cdalp1 + calp1 * sdalp1;
+                            if nsalp1 > 0.0 {
+                                calp1 = calp1 * cdalp1 - salp1 * sdalp1;
+                                salp1 = nsalp1;
+                                norm(&mut salp1, &mut calp1);
+                                // Convergence may be linear when the slope
+                                // vanishes
+                                tripn = v.abs() <= 16.0 * TOL0;
+                                continue;
+                            }
+                        }
+                    }
+
+                    salp1 = (salp1a + salp1b) / 2.0;
+                    calp1 = (calp1a + calp1b) / 2.0;
+                    norm(&mut salp1, &mut calp1);
+                    tripn = false;
+                    tripb = (salp1a - salp1).abs() + (calp1a - calp1) < tolb
+                        || (salp1 - salp1b).abs() + (calp1 - calp1b) < tolb;
+                }
+
+                salp2 = lambda.salp2;
+                calp2 = lambda.calp2;
+                let (s12b, _, _) = self.lengths(
+                    lambda.eps,
+                    lambda.sig12,
+                    lambda.ssig1,
+                    lambda.csig1,
+                    dn1,
+                    lambda.ssig2,
+                    lambda.csig2,
+                    dn2,
+                );
+                s12x = s12b * self.b;
+            }
+        }
+
+        // Undo the canonical configuration
+        if swapp < 0.0 {
+            std::mem::swap(&mut salp1, &mut salp2);
+            std::mem::swap(&mut calp1, &mut calp2);
+        }
+        salp1 *= swapp * lonsign;
+        calp1 *= swapp * latsign;
+        salp2 *= swapp * lonsign;
+        calp2 *= swapp * latsign;
+
+        (
+            0.0 + s12x,
+            salp1.atan2(calp1).to_degrees(),
+            salp2.atan2(calp2).to_degrees(),
+        )
+    }
+
+    /// Sine and cosine of the reduced latitude, with cos = +epsilon at the
+    /// poles
+    fn reduced_latitude(&self, lat: f64) -> (f64, f64) {
+        let (mut sbet, mut cbet) = sincosd(lat);
+        sbet *= self.f1;
+        norm(&mut sbet, &mut cbet);
+        (sbet, cbet.max(f64::MIN_POSITIVE.sqrt()))
+    }
+
+    /// Distance and reduced length, both scaled by b, and m0 of the
+    /// geodesic with parameter `eps` spanning `sig12` on the auxiliary
+    /// sphere
+    #[allow(clippy::too_many_arguments)]
+    fn lengths(
+        &self,
+        eps: f64,
+        sig12: f64,
+        ssig1: f64,
+        csig1: f64,
+        dn1: f64,
+        ssig2: f64,
+        csig2: f64,
+        dn2: f64,
+    ) -> (f64, f64, f64) {
+        let (mut ca, mut cb) = ([0.0; ORDER + 1], [0.0; ORDER + 1]);
+        let a1 = a1m1f(eps);
+        c1f(eps, &mut ca);
+        let a2 = a2m1f(eps);
+        c2f(eps, &mut cb);
+        let m0 = a1 - a2;
+        let (a1, a2) = (1.0 + a1, 1.0 + a2);
+
+        let b1 = sin_cos_series(ssig2, csig2, &ca) - sin_cos_series(ssig1, csig1, &ca);
+        let b2 = sin_cos_series(ssig2, csig2, &cb) - sin_cos_series(ssig1, csig1, &cb);
+        let s12b = a1 * (sig12 + b1);
+        let j12 = m0 * sig12 + (a1 * b1 - a2 * b2);
+        let m12b = dn2 * (csig1 * ssig2) - dn1 * (ssig1 * csig2) - csig1 * csig2 * j12;
+
+        (s12b, m12b, m0)
+    }
+
+    /// Starting azimuth for Newton's method. Lines short enough to be solved
+    /// directly have a non-negative `sig12`, with `salp2`, `calp2` and `dnm`
+    /// set.
+    #[allow(clippy::too_many_arguments)]
+    fn inverse_start(
+        &self,
+        sbet1: f64,
+        cbet1: f64,
+        dn1: f64,
+        sbet2: f64,
+        cbet2: f64,
+        dn2: f64,
+        lam12: f64,
+        slam12: f64,
+        clam12: f64,
+    ) -> Start {
+        let mut start = Start {
+            sig12: -1.0,
+            salp1: 0.0,
+            calp1: 0.0,
+            salp2: 0.0,
+            calp2: 0.0,
+            dnm: 0.0,
+        };
+
+        // bet12 = bet2 - bet1 in [0, pi), bet12a = bet2 + bet1 in (-pi, 0]
+        let sbet12 = sbet2 * cbet1 - cbet2 * sbet1;
+        let cbet12 = cbet2 * cbet1 + sbet2 * sbet1;
+        let sbet12a = sbet2 * cbet1 + cbet2 * sbet1;
+
+        let shortline = cbet12 >= 0.0 && sbet12 < 0.5 && cbet2 * lam12 < 0.5;
+        let (mut somg12, mut comg12) = if shortline {
+            let mut sbetm2 = (sbet1 + sbet2).powi(2);
+            sbetm2 /= sbetm2 + (cbet1 + cbet2).powi(2);
+            start.dnm = (1.0 + self.ep2 * sbetm2).sqrt();
+            (lam12 / (self.f1 * start.dnm)).sin_cos()
+        } else {
+            (slam12, clam12)
+        };
+
+        start.salp1 = cbet2 * somg12;
+        start.calp1 = if comg12 >= 0.0 {
+            sbet12 + cbet2 * sbet1 * somg12.powi(2) / (1.0 + comg12)
+        } else {
+            sbet12a - cbet2 * sbet1 * somg12.powi(2) / (1.0 - comg12)
+        };
+
+        let ssig12 = start.salp1.hypot(start.calp1);
+        let csig12 = sbet1 * sbet2 + cbet1 * cbet2 * comg12;
+
+        if shortline && ssig12 < self.etol2 {
+            // Really short lines
+            start.salp2 = cbet1 * somg12;
+            start.calp2 = sbet12
+                - cbet1
+                    * sbet2
+                    * if comg12 >= 0.0 {
+                        somg12.powi(2) / (1.0 + comg12)
+                    } else {
+                        1.0 - comg12
+                    };
+            norm(&mut start.salp2, &mut start.calp2);
+            start.sig12 = ssig12.atan2(csig12);
+        } else if self.n.abs() > 0.1
+            || csig12 >= 0.0
+            || ssig12 >= 6.0 * self.n.abs() * PI * cbet1.powi(2)
+        {
+            // The zeroth order spherical approximation is good enough
+        } else {
+            // Nearly antipodal points. Scale lam12 and bet2 to coordinates
+            // where the antipode is at the origin and the singular point at
+            // y = 0, x = -1.
+            let lam12x = (-slam12).atan2(-clam12);
+            let (x, y, lamscale) = if self.f >= 0.0 {
+                let k2 = sbet1.powi(2) * self.ep2;
+                let eps = k2 / (2.0 * (1.0 + (1.0 + k2).sqrt()) + k2);
+                let lamscale = self.f * cbet1 * self.a3f(eps) * PI;
+                let betscale = lamscale * cbet1;
+                (lam12x / lamscale, sbet12a / betscale, lamscale)
+            } else {
+                let cbet12a = cbet2 * cbet1 - sbet2 * sbet1;
+                let bet12a = sbet12a.atan2(cbet12a);
+                let (_, m12b, m0) =
+                    self.lengths(self.n, PI + bet12a, sbet1, -cbet1, dn1, sbet2, cbet2, dn2);
+                let x = -1.0 + m12b / (cbet1 * cbet2 * m0 * PI);
+                let betscale = if x < -0.01 {
+                    sbet12a / x
+                } else {
+                    -self.f * cbet1.powi(2) * PI
+                };
+                let lamscale = betscale / cbet1;
+                (x, lam12x / lamscale, lamscale)
+            };
+
+            let xthresh = 1000.0 * TOL0.sqrt();
+            if y > -TOL1 && x > -1.0 - xthresh {
+                // Strip near the cut
+                if self.f >= 0.0 {
+                    start.salp1 = (-x).min(1.0);
+                    start.calp1 = -(1.0 - start.salp1.powi(2)).sqrt();
+                } else {
+                    start.calp1 = x.max(if x > -TOL1 { 0.0 } else { -1.0 });
+                    start.salp1 = (1.0 - start.calp1.powi(2)).sqrt();
+                }
+            } else {
+                // Estimate omg12 from the astroid problem and use the
+                // spherical formula for alp1
+                let k = astroid(x, y);
+                let omg12a = lamscale
+                    * if self.f >= 0.0 {
+                        -x * k / (1.0 + k)
+                    } else {
+                        -y * (1.0 + k) / k
+                    };
+                let (s, c) = omg12a.sin_cos();
+                somg12 = s;
+                comg12 = -c;
+                start.salp1 = cbet2 * somg12;
+                start.calp1 = sbet12a - cbet2 * sbet1 * somg12.powi(2) / (1.0 - comg12);
+            }
+        }
+
+        // Let NaN through
+        if start.salp1 > 0.0 || start.salp1.is_nan() {
+            norm(&mut start.salp1, &mut start.calp1);
+        } else {
+            start.salp1 = 1.0;
+            start.calp1 = 0.0;
+        }
+
+        start
+    }
+
+    /// Longitude difference reached by the geodesic leaving point 1 with
+    /// azimuth alp1, minus the target lam12, and its derivative with respect
+    /// to alp1 if `diffp` is set
+    #[allow(clippy::too_many_arguments)]
+    fn lambda12(
+        &self,
+        sbet1: f64,
+        cbet1: f64,
+        dn1: f64,
+        sbet2: f64,
+        cbet2: f64,
+        dn2: f64,
+        salp1: f64,
+        calp1: f64,
+        slam120: f64,
+        clam120: f64,
+        diffp: bool,
+    ) -> Lambda {
+        let mut r = Lambda::default();
+
+        // Break the degeneracy of equatorial lines, handled by the caller
+        let calp1 = if sbet1 == 0.0 && calp1 == 0.0 {
+            -f64::MIN_POSITIVE.sqrt()
+        } else {
+            calp1
+        };
+
+        // sin(alp1) * cos(bet1) = sin(alp0)
+        let salp0 = salp1 * cbet1;
+        let calp0 = calp1.hypot(salp1 * sbet1);
+
+        // tan(bet1) = tan(sig1) * cos(alp1)
+        // tan(omg1) = sin(alp0) * tan(sig1)
+        r.ssig1 = sbet1;
+        let somg1 = salp0 * sbet1;
+        r.csig1 = calp1 * cbet1;
+        let comg1 = r.csig1;
+        norm(&mut r.ssig1, &mut r.csig1);
+
+        // Enforce symmetry when |bet2| = -bet1
+        r.salp2 = if cbet2 != cbet1 { salp0 / cbet2 } else { salp1 };
+        r.calp2 = if cbet2 != cbet1 || sbet2.abs() != -sbet1 {
+            ((calp1 * cbet1).powi(2)
+                + if cbet1 < -sbet1 {
+                    (cbet2 - cbet1) * (cbet1 + cbet2)
+                } else {
+                    (sbet1 - sbet2) * (sbet1 + sbet2)
+                })
+            .sqrt()
+                / cbet2
+        } else {
+            calp1.abs()
+        };
+
+        // tan(bet2) = tan(sig2) * cos(alp2)
+        // tan(omg2) = sin(alp0) * tan(sig2)
+        r.ssig2 = sbet2;
+        let somg2 = salp0 * sbet2;
+        r.csig2 = r.calp2 * cbet2;
+        let comg2 = r.csig2;
+        norm(&mut r.ssig2, &mut r.csig2);
+
+        // sig12 = sig2 - sig1 and omg12 = omg2 - omg1, limited to [0, pi].
+        // Adding 0.0 turns -0.0 into 0.0, keeping atan2 in [0, pi].
+        r.sig12 = ((r.csig1 * r.ssig2 - r.ssig1 * r.csig2).max(0.0) + 0.0)
+            .atan2(r.csig1 * r.csig2 + r.ssig1 * r.ssig2);
+        let somg12 = (comg1 * somg2 - somg1 * comg2).max(0.0) + 0.0;
+        let comg12 = comg1 * comg2 + somg1 * somg2;
+
+        // eta = omg12 - lam120
+        let eta = (somg12 * clam120 - comg12 * slam120).atan2(comg12 * clam120 + somg12 * slam120);
+        let k2 = calp0.powi(2) * self.ep2;
+        r.eps = k2 / (2.0 * (1.0 + (1.0 + k2).sqrt()) + k2);
+
+        let mut c3 = [0.0; ORDER + 1];
+        self.c3f(r.eps, &mut c3);
+        let b312 = sin_cos_series(r.ssig2, r.csig2, &c3[..ORDER])
+            - sin_cos_series(r.ssig1, r.csig1, &c3[..ORDER]);
+        let domg12 = -self.f * self.a3f(r.eps) * salp0 * (r.sig12 + b312);
+        r.lam12 = eta + domg12;
+
+        if diffp {
+            r.dlam12 = if r.calp2 == 0.0 {
+                -2.0 * self.f1 * dn1 / sbet1
+            } else {
+                let (_, m12b, _) =
+                    self.lengths(r.eps, r.sig12, r.ssig1, r.csig1, dn1, r.ssig2, r.csig2, dn2);
+                m12b * self.f1 / (r.calp2 * cbet2)
+            };
+        }
+
+        r
+    }
+}
+
+/// Sum of the sine series `c[1] sin(2x) + c[2] sin(4x) + ...` with
+/// Clenshaw summation; `c[0]` is unused
+fn sin_cos_series(sinx: f64, cosx: f64, c: &[f64]) -> f64 {
+    let n = c.len() - 1;
+    let ar = 2.0 * (cosx - sinx) * (cosx + sinx);
+    let mut k = n + 1;
+    let mut y0 = if n % 2 == 1 {
+        k -= 1;
+        c[k]
+    } else {
+        0.0
+    };
+    let mut y1 = 0.0;
+    for _ in 0..n / 2 {
+        k -= 1;
+        y1 = ar * y0 - y1 + c[k];
+        k -= 1;
+        y0 = ar * y1 - y0 + c[k];
+    }
+    2.0 * sinx * cosx * y0
+}
+
+/// Positive root k of k^4 + 2k^3 - (x^2 + y^2 - 1)k^2 - 2y^2 k - y^2 = 0
+fn astroid(x: f64, y: f64) -> f64 {
+    let p = x.powi(2);
+    let q = y.powi(2);
+    let r = (p + q - 1.0) / 6.0;
+    if q == 0.0 && r <= 0.0 {
+        // y = 0 with |x| <= 1
+        return 0.0;
+    }
+
+    let s = p * q / 4.0;
+    let r2 = r.powi(2);
+    let r3 = r * r2;
+    // Zero on the evolute curve p^(1/3) + q^(1/3) = 1
+    let disc = s * (s + 2.0 * r3);
+    let mut u = r;
+    if disc >= 0.0 {
+        // Pick the sign of the root that avoids cancellation
+        let mut t3 = s + r3;
+        t3 += if t3 < 0.0 { -disc.sqrt() } else { disc.sqrt() };
+        let t = t3.cbrt();
+        u += t + if t != 0.0 { r2 / t } else { 0.0 };
+    } else {
+        let ang = (-disc).sqrt().atan2(-(s + r3));
+        u += 2.0 * r * (ang / 3.0).cos();
+    }
+
+    let v = (u.powi(2) + q).sqrt();
+    let uv = if u < 0.0 { q / (v - u) } else { u + v };
+    let w = (uv - q) / (2.0 * v);
+
+    uv / ((uv + w.powi(2)).sqrt() + w)
+}
+
+/// (1 - eps) * A1 - 1
+fn a1m1f(eps: f64) -> f64 {
+    const COEFF: [f64; 5] = [1.0, 4.0, 64.0, 0.0, 256.0];
+    let m = ORDER / 2;
+    let t = math::polyval(m, &COEFF, eps.powi(2)) / COEFF[m + 1];
+    (t + eps) / (1.0 - eps)
+}
+
+/// (1 + eps) * A2 - 1
+fn a2m1f(eps: f64) -> f64 {
+    const COEFF: [f64; 5] = [-11.0, -28.0, -192.0, 0.0, 256.0];
+    let m = ORDER / 2;
+    let t = math::polyval(m, &COEFF, eps.powi(2)) / COEFF[m + 1];
+    (t - eps) / (1.0 + eps)
+}
+
+/// Coefficients C1[l] of the distance series
+fn c1f(eps: f64, c: &mut [f64; ORDER + 1]) {
+    const COEFF: [f64; 18] = [
+        -1.0, 6.0, -16.0, 32.0, // C1[1] / eps
+        -9.0, 64.0, -128.0, 2048.0, // C1[2] / eps^2
+        9.0, -16.0, 768.0, // C1[3] / eps^3
+        3.0, -5.0, 512.0, // C1[4] / eps^4
+        -7.0, 1280.0, // C1[5] / eps^5
+        -7.0, 2048.0, // C1[6] / eps^6
+    ];
+    series(&COEFF, eps, c);
+}
+
+/// Coefficients C2[l] of the reduced length series
+fn c2f(eps: f64, c: &mut [f64; ORDER + 1]) {
+    const COEFF: [f64; 18] = [
+        1.0, 2.0, 16.0, 32.0, // C2[1] / eps
+        35.0, 64.0, 384.0, 2048.0, // C2[2] / eps^2
+        15.0, 80.0, 768.0, // C2[3] / eps^3
+        7.0, 35.0, 512.0, // C2[4] / eps^4
+        63.0, 1280.0, // C2[5] / eps^5
+        77.0, 2048.0, // C2[6] / eps^6
+    ];
+    series(&COEFF, eps, c);
+}
+
+/// Evaluate the coefficients of eps^l, l in 1..=ORDER, each a polynomial
+/// in eps^2 followed by its divisor
+fn series(coeff: &[f64], eps: f64, c: &mut [f64; ORDER + 1]) {
+    let eps2 = eps.powi(2);
+    let (mut d, mut o) = (eps, 0);
+    for (l, item) in c.iter_mut().enumerate().skip(1) {
+        let m = (ORDER - l) / 2;
+        *item = d * math::polyval(m, &coeff[o..], eps2) / coeff[o + m + 1];
+        o += m + 2;
+        d *= eps;
+    }
+}
+
+/// Round tiny values so that the ones below 1/16 degree keep an exact
+/// representation, as if they were on a grid
+fn ang_round(x: f64) -> f64 {
+    const Z: f64 = 1.0 / 16.0;
+    let y = x.abs();
+    let y = if y < Z { Z - (Z - y) } else { y };
+    y.copysign(x)
+}
+
+/// Sine and cosine of an angle in degrees, exact at multiples of 90
+fn sincosd(x: f64) -> (f64, f64) {
+    let r = x % 360.0;
+    let q = (r / 90.0).round();
+    let (s, c) = (r - 90.0 * q).to_radians().sin_cos();
+    let (sinx, cosx) = match (q as i64).rem_euclid(4) {
+        0 => (s, c),
+        1 => (c, -s),
+        2 => (-s, -c),
+        _ => (-c, s),
+    };
+    (sinx + 0.0, cosx + 0.0)
+}
+
+fn norm(sinx: &mut f64, cosx: &mut f64) {
+    let r = sinx.hypot(*cosx);
+    *sinx /= r;
+    *cosx /= r;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sincosd_exact() {
+        assert_eq!(sincosd(0.0), (0.0, 1.0));
+        assert_eq!(sincosd(90.0), (1.0, 0.0));
+        assert_eq!(sincosd(180.0), (0.0, -1.0));
+        assert_eq!(sincosd(-90.0), (-1.0, 0.0));
+        assert_eq!(sincosd(450.0), (1.0, 0.0));
+        let (s, c) = sincosd(30.0);
+        assert!((s - 0.5).abs() < 1e-15);
+        assert!((c - 0.75_f64.sqrt()).abs() < 1e-15);
+    }
+
+    #[test]
+    fn inverse_symmetry() {
+        let datum = Datum::wgs84();
+        let points = [
+            (-23.0095839, -43.4361816, 52.517153, 13.412389),
+            (0.5, 0.0, -0.5, 179.7),
+            (89.0, 10.0, -89.0, -170.0),
+            (0.0, 0.0, 0.0, 90.0),
+        ];
+        for &(lat1, lon1, lat2, lon2) in points.iter() {
+            let (s12, _, _) = inverse(&datum, lat1, lon1, lat2, lon2);
+            let (s21, _, _) = inverse(&datum, lat2, lon2, lat1, lon1);
+            assert!(s12 > 0.0);
+            assert!((s12 - s21).abs() < 1e-6);
+        }
+    }
+}
@@ -11,7 +11,7 @@
 //!     let lon: f64 = -43.4361816;
 //!     
 //!     let coord = coord::Coord::new(lat, lon);
-//!     let utm: utm::Utm = coord.clone().into();
+//!     let utm: utm::Utm = (&coord).into();
 //!     println!("coord: {}", coord);
 //!     println!("utm: {}", utm);
 //!     // Will print:
@@ -24,12 +24,20 @@
 pub mod coord;
 /// Datum conventions
 pub mod datum;
+/// Earth-centered, earth-fixed coordinates (ECEF)
+pub mod ecef;
+/// Geodesics on the ellipsoid
+mod geodesic;
 /// Mathematical auxiliary functions
 pub mod math;
 /// Military Grid Reference System (MGRS)
 pub mod mgrs;
+/// Projection trait for planar coordinate systems
+pub mod projection;
 /// Universal Transverse Mercator (UTM)
 pub mod utm;
+/// Web Mercator (EPSG:3857)
+pub mod web_mercator;
 
 #[cfg(test)]
 mod tests {
@@ -42,8 +50,8 @@ mod tests {
         let lat: f64 = -23.0095839;
         let lon: f64 = -43.4361816;
         let coord = coord::Coord::new(lat, lon);
-        let utm: Utm = coord.clone().into();
-        let coord2: Coord = utm.clone().into();
+        let utm: Utm = coord.into();
+        let coord2: Coord = utm.into();
 
         println!("coord: {}, utm: {}, coord2: {}", coord, utm, coord2);
     }
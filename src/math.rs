@@ -1,5 +1,3 @@
-use std::f64::EPSILON;
-
 ///
 /// Inverse trigonometric tangent
 ///
@@ -65,16 +63,16 @@ pub fn taupf(tau: f64, es: f64) -> f64 {
 ///
 pub fn tauf(taup: f64, es: f64) -> f64 {
     let numit = 5;
-    let tol: f64 = EPSILON.sqrt() / 10.0;
+    let tol: f64 = f64::EPSILON.sqrt() / 10.0;
     let e2m: f64 = 1.0 - es.powi(2);
     let mut tau: f64 = taup / e2m;
     let stol: f64 = tol * taup.abs().max(1.0);
     for _ in (0..numit).rev() {
         let taupa: f64 = taupf(tau, es);
-        let dtau: f64 = (taup - taupa) * (1.0 + e2m * tau.sqrt())
+        let dtau: f64 = (taup - taupa) * (1.0 + e2m * tau.powi(2))
             / (e2m * 1.0_f64.hypot(tau) * 1.0_f64.hypot(taupa));
-        tau = tau + dtau;
-        if !(dtau.abs() >= stol) {
+        tau += dtau;
+        if dtau.abs() < stol || dtau.is_nan() {
             break;
         }
     }
@@ -210,6 +208,17 @@ mod tests {
         assert_eq!((x * 10000000000.0).trunc(), -643892020.0);
     }
 
+    #[test]
+    fn test_tauf() {
+        // WGS84 eccentricity. tauf inverts taupf to full precision, in both
+        // hemispheres.
+        let es: f64 = (1.0_f64 / 298.257223563 * (2.0 - 1.0 / 298.257223563)).sqrt();
+        for &tau in [-10.0, -1.5, -0.3, 0.0, 0.3, 1.5, 10.0].iter() {
+            let x: f64 = tauf(taupf(tau, es), es);
+            assert!((x - tau).abs() <= 1e-14 * tau.abs().max(1.0), "{}", x);
+        }
+    }
+
     #[test]
     fn test_fmod() {
         let a: f64 = 5.3;
@@ -1,11 +1,146 @@
-use crate::coord::Coord;
+use crate::coord::{self, Coord};
+use crate::datum::Datum;
 use crate::math::fmod;
-use crate::utm::Utm;
+use crate::utm::{self, Utm};
 
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
 
+use thiserror::Error;
+
+/// Largest supported number of easting (and northing) digits
+pub(crate) const MAX_PRECISION: usize = 11;
+
+/// Placeholder written by `Display` for a reference that cannot be labelled
+pub const INVALID_LABEL: &str = "<invalid MGRS>";
+
+/// UTM latitude band letters, from south to north
+const LATBAND: [char; 20] = [
+    'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W',
+    'X',
+];
+/// UTM 100k column letters, one set per `(zone - 1) % 3`
+const UTMCOLS: [[char; 8]; 3] = [
+    ['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H'],
+    ['J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R'],
+    ['S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z'],
+];
+/// UTM 100k row letters
+const UTMROW: [char; 20] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R', 'S', 'T', 'U',
+    'V',
+];
+
+/// UPS band letters: south-west, south-east, north-west and north-east
+const UPSBAND: [char; 4] = ['A', 'B', 'Y', 'Z'];
+/// UPS 100k column letters, one set per UPS band
+const UPSCOLS: [&[char]; 4] = [
+    &['J', 'K', 'L', 'P', 'Q', 'R', 'S', 'T', 'U', 'X', 'Y', 'Z'],
+    &['A', 'B', 'C', 'F', 'G', 'H', 'J', 'K', 'L', 'P', 'Q', 'R'],
+    &['R', 'S', 'T', 'U', 'X', 'Y', 'Z'],
+    &['A', 'B', 'C', 'F', 'G', 'H', 'J'],
+];
+/// UPS 100k row letters, south and north
+const UPSROWS: [&[char]; 2] = [
+    &[
+        'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R', 'S', 'T',
+        'U', 'V', 'W', 'X', 'Y', 'Z',
+    ],
+    &[
+        'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P',
+    ],
+];
+/// First 100k square index, east and north, of the southern and northern
+/// UPS grids
+const UPS_MIN_SQUARE: [f64; 2] = [8.0, 13.0];
+/// 100k square index of the UPS false origin
+const UPS_ORIGIN_SQUARE: f64 = 20.0;
+
+/// Errors produced when parsing an MGRS string
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum FromStringError {
+    #[error("Not enough input to parse an MGRS reference")]
+    NotEnoughInput,
+    #[error("Invalid zone number: {0}")]
+    InvalidZone(i32),
+    #[error("Invalid zone letter: {0}")]
+    InvalidZoneLetter(char),
+    #[error("Invalid 100k column letter: {0}")]
+    InvalidColumnLetter(char),
+    #[error("Invalid 100k row letter: {0}")]
+    InvalidRowLetter(char),
+    #[error("Invalid digit: {0}")]
+    InvalidDigit(char),
+    #[error("Expected an even number of digits, found {0}")]
+    OddNumberOfDigits(usize),
+}
+
+/// Errors produced by MGRS conversions
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MgrsError {
+    #[error("Invalid cell id: {0:#x}")]
+    InvalidCellId(u64),
+    #[error("Invalid precision: {0}")]
+    InvalidPrecision(usize),
+    #[error("{0} out of range for MGRS: {1}")]
+    OutOfRange(&'static str, f64),
+}
+
+/// Standard MGRS precisions, named after the size of the referenced cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MgrsPrecision {
+    /// 100 km square, no digits
+    Km100,
+    /// 10 km cell, 1 digit
+    Km10,
+    /// 1 km cell, 2 digits
+    Km1,
+    /// 100 m cell, 3 digits
+    M100,
+    /// 10 m cell, 4 digits
+    M10,
+    /// 1 m cell, 5 digits
+    M1,
+}
+
+impl MgrsPrecision {
+    /// Number of easting (and northing) digits, from 0 to 5
+    pub fn digits(self) -> usize {
+        self as usize
+    }
+
+    /// Side of the referenced cell, in meters
+    pub fn cell_size_m(self) -> f64 {
+        10.0_f64.powi(5 - self as i32)
+    }
+}
+
+impl From<MgrsPrecision> for usize {
+    fn from(precision: MgrsPrecision) -> Self {
+        precision.digits()
+    }
+}
+
+impl TryFrom<usize> for MgrsPrecision {
+    type Error = MgrsError;
+
+    fn try_from(digits: usize) -> Result<Self, Self::Error> {
+        match digits {
+            0 => Ok(MgrsPrecision::Km100),
+            1 => Ok(MgrsPrecision::Km10),
+            2 => Ok(MgrsPrecision::Km1),
+            3 => Ok(MgrsPrecision::M100),
+            4 => Ok(MgrsPrecision::M10),
+            5 => Ok(MgrsPrecision::M1),
+            _ => Err(MgrsError::InvalidPrecision(digits)),
+        }
+    }
+}
+
 /// UTM/UPS extension for MGRS formatting
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mgrs {
     /// utm: Base UTM/UPS information for MGRS.
     pub utm: Utm,
@@ -15,13 +150,158 @@ pub struct Mgrs {
 impl Mgrs {
     /// Mgrs constructor.
     pub fn new(utm: Utm) -> Mgrs {
-        Mgrs { utm: utm, prec: 5 }
+        Mgrs { utm, prec: 5 }
     }
-}
 
-impl fmt::Display for Mgrs {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let max_prec: usize = 11;
+    /// Mgrs constructor with one of the standard precisions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let utm = utm::Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+    /// let mgrs = mgrs::Mgrs::with_precision(utm, mgrs::MgrsPrecision::M100);
+    /// assert_eq!(mgrs.to_string(), "23KPQ602545");
+    /// ```
+    pub fn with_precision(utm: Utm, precision: MgrsPrecision) -> Mgrs {
+        Mgrs {
+            utm,
+            prec: precision.digits(),
+        }
+    }
+
+    /// Pack the MGRS cell into a 64 bit integer key.
+    ///
+    /// Bit layout, from the most significant bit:
+    ///
+    /// * 63..58: unused, always 0
+    /// * 57..52: zone number, 0 for UPS
+    /// * 51..47: latitude band letter, as an offset from 'A'
+    /// * 46..42: 100k column letter, as an offset from 'A'
+    /// * 41..37: 100k row letter, as an offset from 'A'
+    /// * 36..34: precision
+    /// * 33..17: easting digits within the 100k square
+    /// * 16..0: northing digits within the 100k square
+    ///
+    /// Precisions above 5 are truncated to 5 (1 m cells). Fails with the
+    /// same errors as [`Mgrs::to_mgrs_string`] for values that cannot be
+    /// labelled.
+    pub fn to_cell_id(&self) -> Result<u64, MgrsError> {
+        let prec = self.prec.min(5);
+        let grid = Mgrs { prec, ..*self }.grid_ref()?;
+        let letter = |c: char| (c as u64) - ('A' as u64);
+        let [band, col, row] = grid.letters;
+
+        Ok((grid.zone as u64) << 52
+            | letter(band) << 47
+            | letter(col) << 42
+            | letter(row) << 37
+            | (prec as u64) << 34
+            | grid.easting << 17
+            | grid.northing)
+    }
+
+    /// Unpack an MGRS cell from a key produced by `to_cell_id`.
+    ///
+    /// The returned Mgrs points to the south-west corner of the cell.
+    pub fn from_cell_id(id: u64) -> Result<Mgrs, MgrsError> {
+        let invalid = MgrsError::InvalidCellId(id);
+        let field = |shift: u32, bits: u32| (id >> shift) & ((1 << bits) - 1);
+        let letter = |shift: u32| (b'A' + field(shift, 5) as u8) as char;
+
+        let zone = field(52, 6) as i32;
+        let band = letter(47);
+        let col = letter(42);
+        let row = letter(37);
+        let prec = field(34, 3) as usize;
+        let easting = field(17, 17);
+        let northing = field(0, 17);
+
+        if id >> 58 != 0 || zone > 60 || prec > 5 {
+            return Err(invalid);
+        }
+        let cell = 10_u64.pow(5 - prec as u32);
+        if easting * cell >= 100000 || northing * cell >= 100000 {
+            return Err(invalid);
+        }
+
+        let (easting, northing) = ((easting * cell) as f64, (northing * cell) as f64);
+        let utm = if zone == 0 {
+            ups_from_parts(band, col, row, easting, northing)
+        } else {
+            utm_from_parts(zone, band, col, row, easting, northing)
+        }
+        .map_err(|_| invalid)?;
+
+        Ok(Mgrs { utm, prec })
+    }
+
+    /// Format this reference, checking that the UTM values can be labelled.
+    ///
+    /// Unlike the `Display` implementation, which writes [`INVALID_LABEL`] for
+    /// a reference out of range, an error is returned for a zone outside
+    /// 1..60, an easting outside the 100k columns, a northing outside
+    /// 0..10,000,000 m, or a precision above 11.
+    pub fn to_mgrs_string(&self) -> Result<String, MgrsError> {
+        self.check()?;
+        Ok(self.to_string())
+    }
+
+    /// Geographic center of the cell referenced at this precision.
+    pub fn cell_center(&self) -> Coord {
+        self.cell_center_utm().into()
+    }
+
+    /// Ground area, in square meters, of the cell referenced at this
+    /// precision.
+    ///
+    /// The cell is a square on the grid; its side is divided by the WGS84
+    /// point scale factor at the cell center to get the ground size.
+    pub fn cell_area_m2(&self) -> f64 {
+        let scale = self.cell_center_utm().scale_factor(Datum::wgs84_ref());
+        (cell_size(self.prec) / scale).powi(2)
+    }
+
+    /// Check the ranges indexed by the `Display` implementation
+    fn check(&self) -> Result<(), MgrsError> {
+        let utm = &self.utm;
+        if self.prec > MAX_PRECISION {
+            return Err(MgrsError::InvalidPrecision(self.prec));
+        }
+        if !utm.easting.is_finite() {
+            return Err(MgrsError::OutOfRange("easting", utm.easting));
+        }
+        if !(0.0..utm::SOUTH_FALSE_NORTHING).contains(&utm.northing) {
+            return Err(MgrsError::OutOfRange("northing", utm.northing));
+        }
+        if utm.ups {
+            let min = UPS_MIN_SQUARE[utm.north as usize];
+            let max = 2.0 * UPS_ORIGIN_SQUARE - min;
+            if !(min..max).contains(&(utm.easting / 100000.0).floor()) {
+                return Err(MgrsError::OutOfRange("easting", utm.easting));
+            }
+            if !(min..max).contains(&(utm.northing / 100000.0).floor()) {
+                return Err(MgrsError::OutOfRange("northing", utm.northing));
+            }
+        } else {
+            if !(1..=60).contains(&utm.zone) {
+                return Err(MgrsError::OutOfRange("zone", utm.zone as f64));
+            }
+            if !(100000.0..900000.0).contains(&utm.easting) {
+                return Err(MgrsError::OutOfRange("easting", utm.easting));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Zone, letters and digits of the reference, after checking that the
+    /// UTM values can be labelled
+    fn grid_ref(&self) -> Result<GridRef, MgrsError> {
+        self.check()?;
+
+        let max_prec: usize = MAX_PRECISION;
         let mult: f64 = 1000000.0;
         let tile: f64 = 100000.0;
         let utm_row_period: f64 = 20.0;
@@ -32,31 +312,11 @@ impl fmt::Display for Mgrs {
         let utm = &self.utm;
 
         let zone1 = &utm.zone - 1;
-        let mut z: usize = if utm.ups { 0 } else { 2 };
         let base: usize = 10;
 
-        let digits = vec!['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
-        let latband = vec![
-            'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R', 'S', 'T', 'U',
-            'V', 'W', 'X',
-        ];
-        let utmcols = vec![
-            vec!['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H'],
-            vec!['J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R'],
-            vec!['S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z'],
-        ];
-        let utmrow = vec![
-            'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R', 'S',
-            'T', 'U', 'V',
-        ];
-
-        let mut mgrs: String = String::from("");
-
-        if utm.ups {
-        } else {
-            mgrs.push(digits[utm.zone as usize / base]);
-            mgrs.push(digits[utm.zone as usize % base]);
-        }
+        let latband = LATBAND;
+        let utmcols = UTMCOLS;
+        let utmrow = UTMROW;
 
         let mut ix: f64 = (utm.easting * mult).floor();
         let mut iy: f64 = (utm.northing * mult).floor();
@@ -64,13 +324,27 @@ impl fmt::Display for Mgrs {
         let xh: f64 = (ix / m).trunc();
         let yh: f64 = (iy / m).trunc();
 
-        let prec = self.prec;
+        let letters = if utm.ups {
+            let north = utm.north as usize;
+            let east = xh >= UPS_ORIGIN_SQUARE;
+            let iband = 2 * north + east as usize;
+            let col = xh
+                - if east {
+                    UPS_ORIGIN_SQUARE
+                } else {
+                    UPS_MIN_SQUARE[north]
+                };
+            let row = yh - UPS_MIN_SQUARE[north];
 
-        if utm.ups {
+            [
+                UPSBAND[iband],
+                UPSCOLS[iband][col as usize],
+                UPSROWS[north][row as usize],
+            ]
         } else {
-            let coord: Coord = self.clone().into();
+            let coord: Coord = (*self).into();
             let ilat = coord.lat.floor();
-            let lband = ((ilat + 80.0) / 8.0 - 10.10).min(9.0).max(-10.0);
+            let lband = (((ilat + 80.0) / 8.0).floor() - 10.0).clamp(-10.0, 9.0);
             let iband = (if coord.lat.abs() > angeps {
                 lband
             } else if utm.north {
@@ -111,8 +385,6 @@ impl fmt::Display for Mgrs {
                 }
             }
 
-            mgrs.push(latband[(10.0 + iband) as usize]);
-            mgrs.push(utmcols[(zone1 % 3) as usize][icol as usize]);
             let pos: usize = fmod(
                 yh + (if (zone1 % 2) > 0 {
                     utm_even_row_shift
@@ -121,37 +393,502 @@ impl fmt::Display for Mgrs {
                 }),
                 utm_row_period,
             ) as usize;
-            mgrs.push(utmrow[pos]);
-            z += 3;
-        }
+            [
+                latband[(10.0 + iband) as usize],
+                utmcols[(zone1 % 3) as usize][icol as usize],
+                utmrow[pos],
+            ]
+        };
 
-        if prec > 0 {
-            ix -= m * xh;
-            iy -= m * yh;
-            let d: f64 = (base as f64).powi((max_prec - &prec) as i32);
-            ix = ix / d;
-            iy = iy / d;
+        ix -= m * xh;
+        iy -= m * yh;
+        let d: f64 = (base as f64).powi((max_prec - self.prec) as i32);
 
-            unsafe {
-                while mgrs.len() < z + prec + prec {
-                    mgrs.push(' ')
-                }
+        Ok(GridRef {
+            zone: if utm.ups { 0 } else { utm.zone },
+            letters,
+            easting: (ix / d).floor() as u64,
+            northing: (iy / d).floor() as u64,
+        })
+    }
 
-                let vec_mgrs = mgrs.as_mut_vec();
+    /// Grid position of the center of the cell
+    fn cell_center_utm(&self) -> Utm {
+        let size = cell_size(self.prec);
+        let mut utm = self.utm.truncated(size);
+        utm.easting += size / 2.0;
+        utm.northing += size / 2.0;
+        utm
+    }
+}
 
-                for c in (0..prec).rev() {
-                    let ind1: usize = (z + c) as usize;
-                    let ind2: usize = (z + c + &prec) as usize;
-                    vec_mgrs[ind1] = digits[(ix % base as f64) as usize] as u8;
-                    ix = ix / base as f64;
-                    vec_mgrs[ind2] = digits[(iy % base as f64) as usize] as u8;
-                    iy = iy / base as f64;
-                }
-            }
+/// Side, in meters, of the MGRS cells at precision `prec`
+fn cell_size(prec: usize) -> f64 {
+    10.0_f64.powi(5 - prec as i32)
+}
+
+/// Parts of a formatted MGRS reference
+struct GridRef {
+    /// UTM zone number, 0 for UPS
+    zone: i32,
+    /// Band, 100k column and 100k row letters
+    letters: [char; 3],
+    /// Easting digits within the 100k square
+    easting: u64,
+    /// Northing digits within the 100k square
+    northing: u64,
+}
+
+/// Hemisphere of a UTM/MGRS coordinate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hemisphere {
+    North,
+    South,
+}
+
+/// What `from_string_verbose` inferred from its input
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedMgrsInfo {
+    /// Input with separators removed and letters uppercased
+    pub normalized_input: String,
+    /// Number of easting (and northing) digits
+    pub precision: usize,
+    /// Hemisphere implied by the latitude band letter
+    pub hemisphere: Hemisphere,
+}
+
+/// Parts of an MGRS reference, as decoded by [`parse_components`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MgrsComponents {
+    /// UTM zone number
+    pub zone: i32,
+    /// Latitude band letter
+    pub band: char,
+    /// Column letter of the 100k square
+    pub col_letter: char,
+    /// Row letter of the 100k square
+    pub row_letter: char,
+    /// Easting, in meters, within the 100k square
+    pub easting: f64,
+    /// Northing, in meters, within the 100k square
+    pub northing: f64,
+    /// Number of easting (and northing) digits
+    pub precision: usize,
+}
+
+///
+/// Parse an MGRS reference such as `"48P UV 77298 83034"`
+///
+/// Whitespace, commas and hyphens are ignored and letters are case
+/// insensitive. The precision of
+/// the returned Mgrs is the number of easting digits in the input, and its
+/// Utm points to the south-west corner of the referenced cell.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let mgrs: mgrs::Mgrs = mgrs::from_string("48P UV 77298 83034").unwrap();
+/// assert_eq!(mgrs.utm.zone, 48);
+/// ```
+///
+pub fn from_string(inp: &str) -> Result<Mgrs, FromStringError> {
+    from_string_verbose(inp).map(|(mgrs, _)| mgrs)
+}
+
+///
+/// Parse an MGRS reference, also reporting what the parser inferred
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let (mgrs, info) = mgrs::from_string_verbose("48p uv 772 830").unwrap();
+/// assert_eq!(info.normalized_input, "48PUV772830");
+/// assert_eq!(info.precision, 3);
+/// ```
+///
+pub fn from_string_verbose(inp: &str) -> Result<(Mgrs, ParsedMgrsInfo), FromStringError> {
+    let (normalized, parts) = split_reference(inp)?;
+    let utm = utm_from_parts(
+        parts.zone,
+        parts.band,
+        parts.col_letter,
+        parts.row_letter,
+        parts.easting,
+        parts.northing,
+    )?;
+    let hemisphere = if utm.north {
+        Hemisphere::North
+    } else {
+        Hemisphere::South
+    };
+
+    Ok((
+        Mgrs {
+            utm,
+            prec: parts.precision,
+        },
+        ParsedMgrsInfo {
+            normalized_input: normalized,
+            precision: parts.precision,
+            hemisphere,
+        },
+    ))
+}
+
+///
+/// Decode the parts of an MGRS reference without assembling a Utm
+///
+/// The input is normalized as in [`from_string`]. Easting and northing are
+/// the offsets, in meters, of the south-west corner of the referenced cell
+/// within its 100k square.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let parts = mgrs::parse_components("48PUV7729883034").unwrap();
+/// assert_eq!(parts.zone, 48);
+/// assert_eq!((parts.col_letter, parts.row_letter), ('U', 'V'));
+/// assert_eq!(parts.easting, 77298.0);
+/// ```
+///
+pub fn parse_components(inp: &str) -> Result<MgrsComponents, FromStringError> {
+    split_reference(inp).map(|(_, parts)| parts)
+}
+
+///
+/// Check the structure of an MGRS reference and return its canonical form
+///
+/// The zone digits, band letter, 100k square letters and digit count are
+/// validated without converting the reference to a coordinate. The
+/// canonical form is uppercased, uses a two digit zone, and separates the
+/// square letters, easting and northing with single spaces.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let canonical = mgrs::validate("48p uv 7729 8303").unwrap();
+/// assert_eq!(canonical, "48P UV 7729 8303");
+/// ```
+///
+pub fn validate(inp: &str) -> Result<String, FromStringError> {
+    let (normalized, parts) = split_reference(inp)?;
+    let prec = parts.precision;
+    let digits = &normalized[normalized.len() - 2 * prec..];
+
+    let mut canonical = format!(
+        "{:02}{} {}{}",
+        parts.zone, parts.band, parts.col_letter, parts.row_letter
+    );
+    if prec > 0 {
+        canonical.push(' ');
+        canonical.push_str(&digits[..prec]);
+        canonical.push(' ');
+        canonical.push_str(&digits[prec..]);
+    }
+
+    Ok(canonical)
+}
+
+/// Normalize an MGRS reference and check its structure, returning the input
+/// with separators removed and letters uppercased along with its parts
+fn split_reference(inp: &str) -> Result<(String, MgrsComponents), FromStringError> {
+    let normalized: String = inp
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, ',' | '-'))
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let chars: Vec<char> = normalized.chars().collect();
+
+    let z = chars.iter().take_while(|c| c.is_ascii_digit()).count();
+    if z == 0 || z > 2 || chars.len() < z + 3 {
+        return Err(FromStringError::NotEnoughInput);
+    }
+    let zone: i32 = normalized[..z]
+        .parse()
+        .map_err(|_| FromStringError::NotEnoughInput)?;
+    if !(1..=60).contains(&zone) {
+        return Err(FromStringError::InvalidZone(zone));
+    }
+
+    let band = chars[z];
+    let col_letter = chars[z + 1];
+    let row_letter = chars[z + 2];
+    if !LATBAND.contains(&band) {
+        return Err(FromStringError::InvalidZoneLetter(band));
+    }
+    get_easting_from_char(col_letter, zone)?;
+    get_northing_from_char(row_letter, zone)?;
+
+    let digits = &chars[z + 3..];
+    if let Some(&c) = digits.iter().find(|c| !c.is_ascii_digit()) {
+        return Err(FromStringError::InvalidDigit(c));
+    }
+    if digits.len() % 2 == 1 {
+        return Err(FromStringError::OddNumberOfDigits(digits.len()));
+    }
+    let precision = digits.len() / 2;
+    let cell: f64 = 10.0_f64.powi(5 - precision as i32);
+    let parse = |s: &[char]| -> f64 {
+        s.iter().fold(0.0, |acc, c| {
+            acc * 10.0 + c.to_digit(10).unwrap_or(0) as f64
+        })
+    };
+    let easting = parse(&digits[..precision]) * cell;
+    let northing = parse(&digits[precision..]) * cell;
+
+    Ok((
+        normalized,
+        MgrsComponents {
+            zone,
+            band,
+            col_letter,
+            row_letter,
+            easting,
+            northing,
+            precision,
+        },
+    ))
+}
+
+/// Rebuild a UTM coordinate from the MGRS zone, band, 100k square letters
+/// and the offsets, in meters, within the square
+fn utm_from_parts(
+    zone: i32,
+    band: char,
+    col: char,
+    row: char,
+    easting: f64,
+    northing: f64,
+) -> Result<Utm, FromStringError> {
+    if !LATBAND.contains(&band) {
+        return Err(FromStringError::InvalidZoneLetter(band));
+    }
+    let east_100k = get_easting_from_char(col, zone)?;
+    let north_100k = get_northing_from_char(row, zone)?;
+
+    // The row letters repeat every 2000000 m and a band is less than 1500 km
+    // tall, so the cycle closest to the band center is the band's own.
+    let cycle: f64 = 2000000.0;
+    let center = band_center_northing(band, zone);
+    let offset = north_100k + northing;
+    let northing = offset + ((center - offset) / cycle).round() * cycle;
+
+    Ok(Utm::new(
+        east_100k + easting,
+        northing,
+        band >= 'N',
+        zone,
+        band,
+        false,
+    ))
+}
+
+/// Rebuild a UPS coordinate from the MGRS band, 100k square letters and the
+/// offsets, in meters, within the square
+fn ups_from_parts(
+    band: char,
+    col: char,
+    row: char,
+    easting: f64,
+    northing: f64,
+) -> Result<Utm, FromStringError> {
+    let iband = UPSBAND
+        .iter()
+        .position(|&b| b == band)
+        .ok_or(FromStringError::InvalidZoneLetter(band))?;
+    let north = iband / 2;
+    let east = iband % 2 == 1;
+
+    let icol = UPSCOLS[iband]
+        .iter()
+        .position(|&c| c == col)
+        .ok_or(FromStringError::InvalidColumnLetter(col))?;
+    let irow = UPSROWS[north]
+        .iter()
+        .position(|&r| r == row)
+        .ok_or(FromStringError::InvalidRowLetter(row))?;
+
+    let min_col = if east {
+        UPS_ORIGIN_SQUARE
+    } else {
+        UPS_MIN_SQUARE[north]
+    };
+    let xh = min_col + icol as f64;
+    let yh = UPS_MIN_SQUARE[north] + irow as f64;
+
+    Ok(Utm::new(
+        xh * 100000.0 + easting,
+        yh * 100000.0 + northing,
+        north == 1,
+        0,
+        band,
+        true,
+    ))
+}
+
+/// Easting, in meters, of a 100k column letter in the given zone
+fn get_easting_from_char(c: char, zone: i32) -> Result<f64, FromStringError> {
+    let set = UTMCOLS[((zone - 1) % 3) as usize];
+    match set.iter().position(|&x| x == c) {
+        Some(col) => Ok((col + 1) as f64 * 100000.0),
+        None => Err(FromStringError::InvalidColumnLetter(c)),
+    }
+}
+
+/// Northing, in meters and modulo 2000000, of a 100k row letter in the given zone
+fn get_northing_from_char(c: char, zone: i32) -> Result<f64, FromStringError> {
+    let shift = if (zone - 1) % 2 > 0 { 5 } else { 0 };
+    match UTMROW.iter().position(|&x| x == c) {
+        Some(row) => Ok(((row + 20 - shift) % 20) as f64 * 100000.0),
+        None => Err(FromStringError::InvalidRowLetter(c)),
+    }
+}
+
+/// Northing, in meters, of the middle latitude of a band on the central
+/// meridian of `zone`
+fn band_center_northing(band: char, zone: i32) -> f64 {
+    let index = LATBAND.iter().position(|&b| b == band).unwrap_or(0);
+    let lat_min = -80.0 + 8.0 * index as f64;
+    // Band X spans 12 degrees, up to 84N
+    let lat_max = if band == 'X' { 84.0 } else { lat_min + 8.0 };
+    let lat = (lat_min + lat_max) / 2.0;
+
+    let lon_0 = utm::central_meridian(zone);
+    let (_, y, _, _) = utm::forward(Datum::wgs84_ref(), lon_0, lat, lon_0);
+    if lat < 0.0 {
+        y + utm::SOUTH_FALSE_NORTHING
+    } else {
+        y
+    }
+}
+
+/// Writes the MGRS label of the reference.
+///
+/// A reference that cannot be labelled, because its UTM values or precision
+/// are out of range, is written as the placeholder [`INVALID_LABEL`]. Use
+/// [`Mgrs::to_mgrs_string`] to get an error instead.
+impl fmt::Display for Mgrs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let grid = match self.grid_ref() {
+            Ok(grid) => grid,
+            Err(_) => return write!(f, "{}", INVALID_LABEL),
+        };
+
+        if !self.utm.ups {
+            write!(f, "{:02}", grid.zone)?;
+        }
+        let [band, col, row] = grid.letters;
+        write!(f, "{}{}{}", band, col, row)?;
+        if self.prec > 0 {
+            write!(
+                f,
+                "{:0prec$}{:0prec$}",
+                grid.easting,
+                grid.northing,
+                prec = self.prec
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// Count points per MGRS cell
+///
+/// Every point is labelled at precision `prec` and the labels are counted.
+/// Points that cannot be labelled, such as NaN coordinates, are not counted.
+///
+/// # Panics
+///
+/// Panics if `prec` is greater than 11
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let points = vec![
+///     coord::Coord::new(-23.0095839, -43.4361816),
+///     coord::Coord::new(-23.0095, -43.4361),
+/// ];
+/// let counts = mgrs::histogram(points, 3);
+/// assert_eq!(counts["23KPQ602545"], 2);
+/// ```
+///
+pub fn histogram<I: IntoIterator<Item = Coord>>(iter: I, prec: usize) -> HashMap<String, usize> {
+    assert!(prec <= MAX_PRECISION, "invalid precision: {}", prec);
+
+    let datum = Datum::wgs84_ref();
+    let mut counts = HashMap::new();
+    for coord in iter {
+        let utm = utm::from_coord(&coord, datum);
+        if let Ok(label) = (Mgrs { utm, prec }).to_mgrs_string() {
+            *counts.entry(label).or_insert(0) += 1;
         }
+    }
+
+    counts
+}
 
-        write!(f, "{}", mgrs)
+///
+/// Centroid of a set of MGRS cells weighted by their ground area
+///
+/// The center of every cell is weighted by [`Mgrs::cell_area_m2`] and the
+/// centroid is computed on the sphere with [`coord::weighted_centroid`].
+/// Returns `None` for an empty input.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let cells = [
+///     mgrs::from_string("23KPQ").unwrap(),
+///     mgrs::from_string("23KPR").unwrap(),
+/// ];
+/// let center = mgrs::area_weighted_center(&cells).unwrap();
+/// ```
+///
+pub fn area_weighted_center(cells: &[Mgrs]) -> Option<Coord> {
+    let weighted: Vec<(Coord, f64)> = cells
+        .iter()
+        .map(|cell| (cell.cell_center(), cell.cell_area_m2()))
+        .collect();
+
+    coord::weighted_centroid(&weighted)
+}
+
+///
+/// Coarsest MGRS precision whose cell size meets a ground resolution
+///
+/// Cell sizes are 100 km, 10 km, 1 km, 100 m, 10 m and 1 m for precisions 0
+/// through 5. Resolutions finer than 1 m return 5.
+///
+/// # Example
+///
+/// ```
+/// let prec: usize = geomorph::mgrs::precision_for_resolution(10.0);
+/// assert_eq!(prec, 4);
+/// ```
+///
+pub fn precision_for_resolution(meters: f64) -> usize {
+    let mut cell: f64 = 100000.0;
+    for prec in 0..5 {
+        if cell <= meters {
+            return prec;
+        }
+        cell /= 10.0;
     }
+    5
 }
 
 impl From<Utm> for Mgrs {
@@ -166,6 +903,40 @@ impl From<Coord> for Mgrs {
     }
 }
 
+impl From<&Utm> for Mgrs {
+    fn from(utm: &Utm) -> Self {
+        Mgrs::new(*utm)
+    }
+}
+
+impl From<&Coord> for Mgrs {
+    fn from(coord: &Coord) -> Self {
+        Mgrs::new(coord.into())
+    }
+}
+
+impl From<Mgrs> for String {
+    fn from(mgrs: Mgrs) -> Self {
+        mgrs.to_string()
+    }
+}
+
+impl TryFrom<&str> for Mgrs {
+    type Error = FromStringError;
+
+    fn try_from(inp: &str) -> Result<Self, Self::Error> {
+        from_string(inp)
+    }
+}
+
+impl TryFrom<String> for Mgrs {
+    type Error = FromStringError;
+
+    fn try_from(inp: String) -> Result<Self, Self::Error> {
+        from_string(&inp)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,11 +950,63 @@ mod tests {
         let mgrs: Mgrs = utm.into();
         assert_eq!(mgrs.utm.easting.trunc(), 660265.0);
         assert_eq!(mgrs.utm.northing.trunc(), 7454564.0);
-        assert_eq!(mgrs.utm.north, false);
+        assert!(!mgrs.utm.north);
         assert_eq!(mgrs.utm.zone, 23);
         assert_eq!(mgrs.utm.band, 'K');
     }
 
+    #[test]
+    fn from_references() {
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        let mgrs = Mgrs::from(&coord);
+        assert_eq!(mgrs.utm.zone, 23);
+        assert_eq!(coord.lon, -43.4361816);
+
+        let utm: Utm = coord.into();
+        let from_utm = Mgrs::from(&utm);
+        assert_eq!(from_utm.to_string(), mgrs.to_string());
+        assert_eq!(utm.band, 'K');
+    }
+
+    #[test]
+    fn with_precision() {
+        let utm = Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+        let precisions = [
+            MgrsPrecision::Km100,
+            MgrsPrecision::Km10,
+            MgrsPrecision::Km1,
+            MgrsPrecision::M100,
+            MgrsPrecision::M10,
+            MgrsPrecision::M1,
+        ];
+        for (digits, &precision) in precisions.iter().enumerate() {
+            let mgrs = Mgrs::with_precision(utm, precision);
+            assert_eq!(mgrs.prec, digits);
+            assert_eq!(mgrs.to_string().len(), 5 + 2 * digits);
+            assert_eq!(precision.cell_size_m(), 10.0_f64.powi(5 - digits as i32));
+            assert_eq!(MgrsPrecision::try_from(digits), Ok(precision));
+            assert_eq!(usize::from(precision), digits);
+        }
+        assert_eq!(
+            Mgrs::with_precision(utm, MgrsPrecision::Km1).to_string(),
+            "23KPQ6054"
+        );
+        assert_eq!(
+            MgrsPrecision::try_from(6),
+            Err(MgrsError::InvalidPrecision(6))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mgrs = super::from_string("23KPQ6026454563").unwrap();
+        let json = serde_json::to_string(&mgrs).unwrap();
+        let decoded: Mgrs = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.prec, mgrs.prec);
+        assert_eq!(decoded.to_string(), mgrs.to_string());
+    }
+
     #[test]
     fn mgrs_clone() {
         let easting = 660265.0;
@@ -194,8 +1017,9 @@ mod tests {
         let ups = false;
         let utm = Utm::new(easting, northing, north, zone, band, ups);
         let mut mgrs_base: Mgrs = utm.into();
-        let mgrs = mgrs_base.clone();
+        let mgrs = mgrs_base;
         mgrs_base.utm.easting = 0.0;
+        assert_eq!(mgrs_base.utm.easting, 0.0);
         assert_eq!(mgrs.utm.easting, easting);
         assert_eq!(mgrs.utm.northing, northing);
         assert_eq!(mgrs.utm.north, north);
@@ -203,6 +1027,456 @@ mod tests {
         assert_eq!(mgrs.utm.band, band);
     }
 
+    #[test]
+    fn cell_id_round_trip() {
+        let coord = Coord::new(-23.00958611, -43.43618250);
+        for prec in 0..6 {
+            let mut mgrs: Mgrs = coord.into();
+            mgrs.prec = prec;
+            let id = mgrs.to_cell_id().unwrap();
+            let cell = Mgrs::from_cell_id(id).unwrap();
+            assert_eq!(cell.prec, prec);
+            assert_eq!(cell.to_string(), mgrs.to_string());
+            assert_eq!(cell.to_cell_id(), Ok(id));
+        }
+
+        let mgrs: Mgrs = Coord::new(13.41250188, 103.86666901).into();
+        let cell = Mgrs::from_cell_id(mgrs.to_cell_id().unwrap()).unwrap();
+        assert_eq!(cell.to_string(), "48PUV7729883035");
+    }
+
+    #[test]
+    fn cell_id_ups_round_trip() {
+        let coords = [
+            Coord::new(87.0, 30.0),
+            Coord::new(87.0, -30.0),
+            Coord::new(-87.0, -30.0),
+            Coord::new(-85.0, 120.0),
+            Coord::new(90.0, 0.0),
+        ];
+        for coord in coords.iter() {
+            let mgrs: Mgrs = coord.into();
+            let id = mgrs.to_cell_id().unwrap();
+            assert_eq!(id >> 52, 0);
+            let cell = Mgrs::from_cell_id(id).unwrap();
+            assert!(cell.utm.ups);
+            assert_eq!(cell.utm.north, mgrs.utm.north);
+            assert_eq!(cell.to_string(), mgrs.to_string());
+            assert_eq!(cell.to_cell_id(), Ok(id));
+        }
+
+        let mgrs: Mgrs = Coord::new(87.0, 30.0).into();
+        let cell = Mgrs::from_cell_id(mgrs.to_cell_id().unwrap()).unwrap();
+        assert_eq!(cell.to_string(), "ZBE6657211488");
+    }
+
+    #[test]
+    fn cell_id_invalid() {
+        let id = Mgrs::new(Utm::new(660265.0, 7454564.0, false, 23, 'K', false))
+            .to_cell_id()
+            .unwrap();
+        assert!(Mgrs::from_cell_id(id).is_ok());
+        assert_eq!(
+            Mgrs::from_cell_id(id | 1 << 60).unwrap_err(),
+            MgrsError::InvalidCellId(id | 1 << 60)
+        );
+        let bad_zone = id | 0x3f << 52;
+        assert!(Mgrs::from_cell_id(bad_zone).is_err());
+        // Zone 0 is UPS, which has no band K
+        let ups_zone = id & !(0x3f << 52);
+        assert!(Mgrs::from_cell_id(ups_zone).is_err());
+        let bad_row = id | 0x1f << 37;
+        assert!(Mgrs::from_cell_id(bad_row).is_err());
+
+        // Labelling errors are reported instead of packing a bogus id
+        let forced = Mgrs::new(Coord::new(0.0, 0.0).to_utm_zone(40));
+        assert_eq!(
+            forced.to_cell_id(),
+            Err(MgrsError::OutOfRange("easting", forced.utm.easting))
+        );
+        let absurd = Mgrs::new(Utm::new(500000.0, 0.0, true, 61, 'N', false));
+        assert_eq!(
+            absurd.to_cell_id(),
+            Err(MgrsError::OutOfRange("zone", 61.0))
+        );
+    }
+
+    #[test]
+    fn from_string() {
+        let mgrs = super::from_string("48P UV 77298 83035").unwrap();
+        assert_eq!(mgrs.prec, 5);
+        assert_eq!(mgrs.utm.zone, 48);
+        assert_eq!(mgrs.utm.easting, 377298.0);
+        assert_eq!(mgrs.utm.northing, 1483035.0);
+        assert!(mgrs.utm.north);
+        assert_eq!(mgrs.to_string(), "48PUV7729883035");
+
+        let mgrs = super::from_string("23KPQ6026454563").unwrap();
+        assert_eq!(mgrs.utm.easting, 660264.0);
+        assert_eq!(mgrs.utm.northing, 7454563.0);
+        assert!(!mgrs.utm.north);
+        assert_eq!(mgrs.to_string(), "23KPQ6026454563");
+
+        assert_eq!(
+            super::from_string("48P").unwrap_err(),
+            FromStringError::NotEnoughInput
+        );
+        assert_eq!(
+            super::from_string("48PUV772988303").unwrap_err(),
+            FromStringError::OddNumberOfDigits(9)
+        );
+        assert_eq!(
+            super::from_string("48P II 0 0").unwrap_err(),
+            FromStringError::InvalidColumnLetter('I')
+        );
+        assert_eq!(
+            super::from_string("48P UW 0 0").unwrap_err(),
+            FromStringError::InvalidRowLetter('W')
+        );
+    }
+
+    #[test]
+    fn from_string_southern_bands() {
+        let lons = [-45.0, 18.1, 21.0, 23.9, 144.1, 147.0, 149.9];
+        for band in 0..10 {
+            let lat_min = -80.0 + 8.0 * band as f64;
+            for &lat in [lat_min + 0.001, lat_min + 4.0, lat_min + 7.999].iter() {
+                for &lon in lons.iter() {
+                    let mgrs: Mgrs = Coord::new(lat, lon).into();
+                    let s = mgrs.to_mgrs_string().unwrap();
+                    let parsed = super::from_string(&s).unwrap();
+                    assert_eq!(parsed.utm.band, mgrs.utm.band);
+                    assert!(!parsed.utm.north);
+                    assert!(
+                        (parsed.utm.northing - mgrs.utm.northing).abs() < 1.0,
+                        "{}",
+                        s
+                    );
+                    assert!((parsed.utm.easting - mgrs.utm.easting).abs() < 1.0, "{}", s);
+                }
+            }
+        }
+
+        // Row letters shared by bands K and M land in the cycle of each band
+        let k = super::from_string("23K PQ 00000 00000").unwrap();
+        let m = super::from_string("23M PQ 00000 00000").unwrap();
+        assert_eq!(k.utm.northing, 7400000.0);
+        assert_eq!(m.utm.northing, 9400000.0);
+    }
+
+    #[test]
+    fn from_string_precision() {
+        for (inp, expected) in [
+            ("48P UV", "48PUV"),
+            ("48P UV 7 8", "48PUV78"),
+            ("48P UV 772 830", "48PUV772830"),
+            ("48P UV 7729883034", "48PUV7729883034"),
+        ]
+        .iter()
+        {
+            let mgrs = super::from_string(inp).unwrap();
+            assert_eq!(mgrs.prec, (expected.len() - 5) / 2);
+            assert_eq!(mgrs.to_string(), *expected);
+        }
+    }
+
+    #[test]
+    fn from_string_verbose() {
+        let (mgrs, info) = super::from_string_verbose(" 23k pq 602 545 ").unwrap();
+        assert_eq!(info.normalized_input, "23KPQ602545");
+        assert_eq!(info.precision, 3);
+        assert_eq!(info.hemisphere, Hemisphere::South);
+        assert_eq!(mgrs.prec, 3);
+
+        let (_, info) = super::from_string_verbose("48p uv 7729883035").unwrap();
+        assert_eq!(info.normalized_input, "48PUV7729883035");
+        assert_eq!(info.precision, 5);
+        assert_eq!(info.hemisphere, Hemisphere::North);
+    }
+
+    #[test]
+    fn from_string_delimiters() {
+        let expected = super::from_string("48P UV 77298 83034").unwrap().utm;
+        for inp in [
+            "48P,UV,77298,83034",
+            "48P-UV-77298-83034",
+            "48P\tUV\t77298\t83034",
+            " 48p, uv - 77298 ,83034 ",
+        ]
+        .iter()
+        {
+            let (mgrs, info) = super::from_string_verbose(inp).unwrap();
+            assert_eq!(info.normalized_input, "48PUV7729883034");
+            assert_eq!(mgrs.prec, 5);
+            assert_eq!(mgrs.utm.easting, expected.easting);
+            assert_eq!(mgrs.utm.northing, expected.northing);
+        }
+    }
+
+    #[test]
+    fn from_string_zone_numbers() {
+        let single = super::from_string("5KNT0000088518").unwrap();
+        let padded = super::from_string("05KNT0000088518").unwrap();
+        assert_eq!(single.utm.zone, 5);
+        assert_eq!(single.utm.easting, padded.utm.easting);
+        assert_eq!(single.utm.northing, padded.utm.northing);
+        assert_eq!(single.to_string(), "05KNT0000088518");
+
+        assert_eq!(
+            super::from_string("0KNT0000088518").unwrap_err(),
+            FromStringError::InvalidZone(0)
+        );
+        assert_eq!(
+            super::from_string("61KNT0000088518").unwrap_err(),
+            FromStringError::InvalidZone(61)
+        );
+        for inp in ["KNT0000088518", "X5KNT00", "105KNT0000088518", ""].iter() {
+            assert_eq!(
+                super::from_string(inp).unwrap_err(),
+                FromStringError::NotEnoughInput
+            );
+        }
+    }
+
+    #[test]
+    fn from_string_skipped_band_letters() {
+        for band in ['I', 'O'].iter() {
+            let inp = format!("23{}PQ6026454563", band);
+            assert_eq!(
+                super::from_string(&inp).unwrap_err(),
+                FromStringError::InvalidZoneLetter(*band)
+            );
+        }
+    }
+
+    #[test]
+    fn parse_components() {
+        let parts = super::parse_components("48PUV7729883034").unwrap();
+        assert_eq!(
+            parts,
+            MgrsComponents {
+                zone: 48,
+                band: 'P',
+                col_letter: 'U',
+                row_letter: 'V',
+                easting: 77298.0,
+                northing: 83034.0,
+                precision: 5,
+            }
+        );
+        let spaced = super::parse_components("48P UV 77298 83034").unwrap();
+        assert_eq!(spaced, parts);
+
+        // from_string adds the 100k square origin to the same offsets
+        let mgrs = super::from_string("48P UV 77298 83034").unwrap();
+        assert_eq!(mgrs.utm.easting % 100000.0, parts.easting);
+        assert_eq!(mgrs.utm.northing % 100000.0, parts.northing);
+        assert_eq!(mgrs.utm.zone, parts.zone);
+        assert_eq!(mgrs.utm.band, parts.band);
+        assert_eq!(mgrs.prec, parts.precision);
+
+        let parts = super::parse_components("4qfj 12 67").unwrap();
+        assert_eq!(parts.zone, 4);
+        assert_eq!(parts.easting, 12000.0);
+        assert_eq!(parts.northing, 67000.0);
+        assert_eq!(parts.precision, 2);
+
+        assert_eq!(
+            super::parse_components("48PUV772988303").unwrap_err(),
+            FromStringError::OddNumberOfDigits(9)
+        );
+    }
+
+    #[test]
+    fn string_conversions() {
+        use std::convert::TryInto;
+
+        let mgrs: Mgrs = "23KPQ6026454563".try_into().unwrap();
+        let label: String = mgrs.into();
+        assert_eq!(label, "23KPQ6026454563");
+
+        let mgrs = Mgrs::try_from(label).unwrap();
+        assert_eq!(mgrs.utm.zone, 23);
+        assert_eq!(String::from(mgrs), "23KPQ6026454563");
+
+        assert_eq!(
+            Mgrs::try_from("23IPQ").unwrap_err(),
+            FromStringError::InvalidZoneLetter('I')
+        );
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(
+            super::validate("48p uv 7729 8303").unwrap(),
+            "48P UV 7729 8303"
+        );
+        assert_eq!(super::validate("5kpq").unwrap(), "05K PQ");
+        assert_eq!(
+            super::validate("48PIV77298303").unwrap_err(),
+            FromStringError::InvalidColumnLetter('I')
+        );
+        assert_eq!(
+            super::validate("48PUW77298303").unwrap_err(),
+            FromStringError::InvalidRowLetter('W')
+        );
+        assert_eq!(
+            super::validate("48PUV7729830").unwrap_err(),
+            FromStringError::OddNumberOfDigits(7)
+        );
+    }
+
+    #[test]
+    fn histogram() {
+        let center = Coord::new(-23.0095839, -43.4361816);
+        let mut points: Vec<Coord> = (0..10)
+            .map(|i| center.destination(90.0, i as f64))
+            .collect();
+        points.push(Coord::new(-22.9, -43.2));
+        points.push(Coord::new(-22.9, -43.2));
+        points.push(Coord::new(48.8584, 2.2945));
+        points.push(Coord {
+            lat: f64::NAN,
+            lon: 0.0,
+        });
+
+        let counts = super::histogram(points, 3);
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts["23KPQ602545"], 10);
+        assert_eq!(counts.values().sum::<usize>(), 13);
+        assert!(counts.values().any(|&n| n == 2));
+        assert!(counts.values().any(|&n| n == 1));
+
+        assert!(super::histogram(Vec::new(), 5).is_empty());
+    }
+
+    #[test]
+    fn cell_center_and_area() {
+        let mgrs = super::from_string("23KPQ6026454563").unwrap();
+        let center: Utm = mgrs.cell_center().into();
+        assert!((center.easting - 660264.5).abs() < 1e-6);
+        assert!((center.northing - 7454563.5).abs() < 1e-6);
+
+        let mut coarse = mgrs;
+        coarse.prec = 2;
+        let center: Utm = coarse.cell_center().into();
+        assert!((center.easting - 660500.0).abs() < 1e-6);
+        assert!((center.northing - 7454500.0).abs() < 1e-6);
+
+        // 1 km cell near Rio, where k is just below 1
+        let area = coarse.cell_area_m2();
+        assert!(area > 1e6 && area < 1.0002e6);
+    }
+
+    #[test]
+    fn area_weighted_center() {
+        assert!(super::area_weighted_center(&[]).is_none());
+
+        let near_meridian = super::from_string("23KMQ9999950000").unwrap();
+        let near_edge = super::from_string("23KRQ9999950000").unwrap();
+        assert!(near_meridian.cell_area_m2() > near_edge.cell_area_m2());
+
+        let cells = [near_meridian, near_edge];
+        let weighted = super::area_weighted_center(&cells).unwrap();
+        let plain =
+            coord::centroid(&[near_meridian.cell_center(), near_edge.cell_center()]).unwrap();
+        let meridian = near_meridian.cell_center();
+        assert!(weighted.haversine_distance(&meridian) < plain.haversine_distance(&meridian));
+
+        let single = super::area_weighted_center(&cells[..1]).unwrap();
+        assert!(single.haversine_distance(&meridian) < 1e-6);
+    }
+
+    #[test]
+    fn precision_for_resolution() {
+        assert_eq!(super::precision_for_resolution(250000.0), 0);
+        assert_eq!(super::precision_for_resolution(100000.0), 0);
+        assert_eq!(super::precision_for_resolution(1000.0), 2);
+        assert_eq!(super::precision_for_resolution(50.0), 4);
+        assert_eq!(super::precision_for_resolution(10.0), 4);
+        assert_eq!(super::precision_for_resolution(1.0), 5);
+        assert_eq!(super::precision_for_resolution(0.1), 5);
+    }
+
+    #[test]
+    fn to_mgrs_string_out_of_range() {
+        let utm = Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+        let mgrs = Mgrs { utm, prec: 5 };
+        assert_eq!(mgrs.to_mgrs_string().unwrap(), mgrs.to_string());
+
+        let absurd = Mgrs::new(Utm {
+            easting: 1e12,
+            ..utm
+        });
+        assert_eq!(
+            absurd.to_mgrs_string(),
+            Err(MgrsError::OutOfRange("easting", 1e12))
+        );
+        assert_eq!(absurd.to_string(), INVALID_LABEL);
+        assert!(Mgrs::try_from(INVALID_LABEL).is_err());
+
+        let negative = Mgrs::new(Utm {
+            easting: -5.0,
+            ..utm
+        });
+        assert!(negative.to_mgrs_string().is_err());
+        let nan = Mgrs::new(Utm {
+            northing: f64::NAN,
+            ..utm
+        });
+        assert!(nan.to_mgrs_string().is_err());
+        let zone = Mgrs::new(Utm { zone: 123, ..utm });
+        assert_eq!(
+            zone.to_mgrs_string(),
+            Err(MgrsError::OutOfRange("zone", 123.0))
+        );
+        assert_eq!(
+            Mgrs { utm, prec: 12 }.to_mgrs_string(),
+            Err(MgrsError::InvalidPrecision(12))
+        );
+    }
+
+    #[test]
+    fn mgrs_ups() {
+        let label = |lat: f64, lon: f64| Mgrs::new(Coord::new(lat, lon).into()).to_string();
+        assert_eq!(label(90.0, 0.0), "ZAH0000000000");
+        assert_eq!(label(-90.0, 0.0), "BAN0000000000");
+        assert_eq!(label(87.0, 30.0), "ZBE6657211488");
+        assert_eq!(label(87.0, -30.0), "YYE3342711488");
+        assert_eq!(label(-87.0, -30.0), "AYQ3342788511");
+
+        // On the 0/180 meridians the eastern band is used
+        assert!(label(87.0, 0.0).starts_with("ZAD00000"));
+        assert!(label(87.0, 180.0).starts_with("ZAL00000"));
+        assert!(label(87.0, -180.0).starts_with("ZAL00000"));
+        assert!(label(-87.0, 0.0).starts_with("BAR00000"));
+
+        let far = Utm::new(4000000.0, 2000000.0, true, 0, 'Z', true);
+        assert_eq!(
+            Mgrs::new(far).to_mgrs_string(),
+            Err(MgrsError::OutOfRange("easting", 4000000.0))
+        );
+    }
+
+    #[test]
+    fn mgrs_norway_widened_zone() {
+        let coord = Coord::new(60.39299, 5.32415);
+        let mgrs: Mgrs = coord.into();
+        assert_eq!(mgrs.to_string(), "32VKN9747700830");
+
+        let coord = Coord::new(56.5, 4.0);
+        let mgrs: Mgrs = coord.into();
+        assert_eq!(mgrs.utm.zone, 32);
+        assert_eq!(mgrs.to_string(), "32VJH9235172935");
+    }
+
+    #[test]
+    fn mgrs_band_lower_edge() {
+        let coord = Coord::new(-16.5, -43.0);
+        let mgrs: Mgrs = coord.into();
+        assert_eq!(mgrs.to_string(), "23KQB1347974697");
+    }
+
     #[test]
     fn mgrs_to_string_prec6() {
         let lat: f64 = 13.41250188;
@@ -222,4 +1496,15 @@ mod tests {
         mgrs.prec = 5;
         assert_eq!(mgrs.to_string(), "23KPQ6026454563");
     }
+
+    #[test]
+    fn mgrs_to_string_leading_zeros() {
+        let utm = Utm::new(600123.4, 7400045.0, false, 23, 'K', false);
+        let mut mgrs = Mgrs::new(utm);
+        assert_eq!(mgrs.to_string(), "23KPQ0012300045");
+        mgrs.prec = 3;
+        assert_eq!(mgrs.to_string(), "23KPQ001000");
+        mgrs.prec = 7;
+        assert_eq!(mgrs.to_string(), "23KPQ00123400004500");
+    }
 }
@@ -0,0 +1,72 @@
+use crate::coord::Coord;
+use crate::datum::Datum;
+
+///
+/// Map projection between geographic coordinates and a planar system
+///
+/// Implement this trait to plug a coordinate system into the crate. Both
+/// directions take the datum explicitly, so the same type can be used over
+/// any ellipsoid.
+///
+/// # Example
+/// ```
+/// use geomorph::*;
+/// use geomorph::projection::Projection;
+///
+/// let datum = datum::Datum::wgs84();
+/// let coord = coord::Coord::new(-23.0095839, -43.4361816);
+/// let utm = utm::Utm::project(&coord, &datum);
+/// let back = utm.unproject(&datum);
+/// assert!((back.lat - coord.lat).abs() < 1e-9);
+/// ```
+///
+pub trait Projection {
+    /// Project a geographic coordinate over the ellipsoid of `datum`.
+    fn project(coord: &Coord, datum: &Datum) -> Self;
+
+    /// Geographic coordinate of this point over the ellipsoid of `datum`.
+    fn unproject(&self, datum: &Datum) -> Coord;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utm::Utm;
+
+    fn round_trip<P: Projection>(coord: &Coord, datum: &Datum) -> Coord {
+        P::project(coord, datum).unproject(datum)
+    }
+
+    #[test]
+    fn utm_round_trip() {
+        let coords = [
+            Coord::new(-23.0095839, -43.4361816),
+            Coord::new(52.52, 13.40),
+            Coord::new(61.0, 5.0),
+            Coord::new(87.0, 30.0),
+            Coord::new(-85.0, -120.0),
+        ];
+
+        for datum in [Datum::wgs84(), Datum::grs80()].iter() {
+            for coord in coords.iter() {
+                let back = round_trip::<Utm>(coord, datum);
+                assert!((back.lat - coord.lat).abs() < 1e-9, "{} {}", coord, back);
+                assert!((back.lon - coord.lon).abs() < 1e-9, "{} {}", coord, back);
+            }
+        }
+    }
+
+    #[test]
+    fn utm_matches_from() {
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        let projected = Utm::project(&coord, Datum::wgs84_ref());
+        let converted: Utm = coord.into();
+        assert_eq!(projected.easting, converted.easting);
+        assert_eq!(projected.northing, converted.northing);
+
+        let unprojected = converted.unproject(Datum::wgs84_ref());
+        let converted: Coord = converted.into();
+        assert_eq!(unprojected.lat, converted.lat);
+        assert_eq!(unprojected.lon, converted.lon);
+    }
+}
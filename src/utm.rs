@@ -1,15 +1,27 @@
-use crate::coord::Coord;
-use crate::datum::Datum;
+use crate::coord::{self, Coord};
+use crate::datum::{self, Datum};
 use crate::math;
 use crate::mgrs::Mgrs;
+use crate::projection::Projection;
 
 use std::f64::consts;
 use std::fmt;
 
 use num_complex::{Complex, Complex64};
+use thiserror::Error;
+
+/// Errors produced by UTM operations
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum UtmError {
+    #[error("Coordinates span multiple UTM zones: {0} and {1}")]
+    SpansMultipleZones(i32, i32),
+    #[error("Invalid grid spacing: {0}")]
+    InvalidSpacing(f64),
+}
 
 /// Holds attributes for Universal Transverse Mercator (UTM) coordinate system
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Utm {
     pub easting: f64,
     pub northing: f64,
@@ -31,6 +43,901 @@ impl Utm {
             ups,
         }
     }
+
+    /// Project a coordinate into its UTM zone over the ellipsoid of `datum`.
+    ///
+    /// The `From<Coord>` conversion is equivalent to calling this with
+    /// [`Datum::wgs84`].
+    pub fn from_coord_with_datum(coord: &Coord, datum: &Datum) -> Utm {
+        from_coord(coord, datum)
+    }
+
+    /// Utm constructor from a northing relative to the equator.
+    ///
+    /// A negative `northing` places the point in the southern hemisphere, in
+    /// which case the 10,000,000 m false northing is added back.
+    pub fn from_signed_northing(easting: f64, northing: f64, zone: i32, band: char) -> Utm {
+        let north = northing >= 0.0;
+        let northing = if north {
+            northing
+        } else {
+            northing + SOUTH_FALSE_NORTHING
+        };
+
+        Utm::new(easting, northing, north, zone, band, false)
+    }
+
+    /// Northing relative to the equator.
+    ///
+    /// The 10,000,000 m false northing of the southern hemisphere is removed,
+    /// so southern points have a negative northing. UPS coordinates are
+    /// returned unchanged.
+    pub fn northing_signed(&self) -> f64 {
+        if self.north || self.ups {
+            self.northing
+        } else {
+            self.northing - SOUTH_FALSE_NORTHING
+        }
+    }
+
+    /// EPSG code of the WGS84 UTM zone of this point.
+    ///
+    /// The code is 32600 plus the zone number in the northern hemisphere and
+    /// 32700 plus the zone number in the southern one. `None` for UPS.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let utm = utm::Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+    /// assert_eq!(utm.epsg_code(), Some(32723));
+    /// ```
+    pub fn epsg_code(&self) -> Option<u32> {
+        if self.ups || !(1..=60).contains(&self.zone) {
+            return None;
+        }
+
+        let base = if self.north { 32600 } else { 32700 };
+        Some(base + self.zone as u32)
+    }
+
+    /// Copy of this point with easting and northing floored to a multiple of
+    /// `resolution_m` meters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `resolution_m` is not a positive, finite number.
+    pub fn truncated(&self, resolution_m: f64) -> Utm {
+        assert!(
+            resolution_m > 0.0 && resolution_m.is_finite(),
+            "invalid resolution: {}",
+            resolution_m
+        );
+        Utm {
+            easting: (self.easting / resolution_m).floor() * resolution_m,
+            northing: (self.northing / resolution_m).floor() * resolution_m,
+            ..*self
+        }
+    }
+
+    /// Fixed-width binary encoding of this point.
+    ///
+    /// | Bytes  | Content                                  |
+    /// |--------|------------------------------------------|
+    /// | 0..8   | easting, little-endian f64               |
+    /// | 8..16  | northing, little-endian f64              |
+    /// | 16..20 | zone, little-endian i32                  |
+    /// | 20     | band, ASCII                              |
+    /// | 21     | flags: bit 0 `north`, bit 1 `ups`        |
+    pub fn to_bytes(&self) -> [u8; 22] {
+        let mut bytes = [0; 22];
+        bytes[..8].copy_from_slice(&self.easting.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.northing.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.zone.to_le_bytes());
+        bytes[20] = self.band as u8;
+        bytes[21] = self.north as u8 | (self.ups as u8) << 1;
+        bytes
+    }
+
+    /// Decode a point written by [`Utm::to_bytes`].
+    pub fn from_bytes(bytes: &[u8; 22]) -> Utm {
+        let mut easting = [0; 8];
+        let mut northing = [0; 8];
+        let mut zone = [0; 4];
+        easting.copy_from_slice(&bytes[..8]);
+        northing.copy_from_slice(&bytes[8..16]);
+        zone.copy_from_slice(&bytes[16..20]);
+
+        Utm {
+            easting: f64::from_le_bytes(easting),
+            northing: f64::from_le_bytes(northing),
+            north: bytes[21] & 1 != 0,
+            zone: i32::from_le_bytes(zone),
+            band: bytes[20] as char,
+            ups: bytes[21] & 2 != 0,
+        }
+    }
+
+    /// Meridian convergence, in degrees, at this point.
+    ///
+    /// The convergence is the clockwise angle from true north to grid north.
+    pub fn convergence(&self, datum: &Datum) -> f64 {
+        let (_, _, gamma, _) = coord::reverse(self, datum, central_meridian(self.zone));
+        gamma
+    }
+
+    /// Point scale factor of the projection at this point.
+    pub fn scale_factor(&self, datum: &Datum) -> f64 {
+        let (_, _, _, k) = coord::reverse(self, datum, central_meridian(self.zone));
+        k
+    }
+
+    /// Ground distance, in meters, to another point of the same zone.
+    ///
+    /// The grid distance is divided by the mean of the WGS84 scale factors at
+    /// both points. For lines up to a few tens of kilometers the result is
+    /// within about 10 ppm of the geodesic distance.
+    pub fn ground_distance(&self, other: &Utm) -> Result<f64, UtmError> {
+        if self.zone != other.zone {
+            return Err(UtmError::SpansMultipleZones(self.zone, other.zone));
+        }
+
+        let datum = Datum::wgs84_ref();
+        let grid =
+            (self.easting - other.easting).hypot(self.northing_signed() - other.northing_signed());
+        let k = (self.scale_factor(datum) + other.scale_factor(datum)) / 2.0;
+
+        Ok(grid / k)
+    }
+
+    /// Change of longitude, in degrees, caused by shifting this point
+    /// `d_east_m` meters along the easting.
+    ///
+    /// Both positions are converted back to geographic coordinates with
+    /// WGS84, keeping the northing, and their longitude difference is
+    /// returned. It is positive for an eastward shift.
+    pub fn lon_change_for_easting(&self, d_east_m: f64) -> f64 {
+        let shifted = Utm {
+            easting: self.easting + d_east_m,
+            ..*self
+        };
+        let from: Coord = (*self).into();
+        let to: Coord = shifted.into();
+
+        math::angle_diff(from.lon, to.lon)
+    }
+
+    /// Grid azimuth, in degrees clockwise from grid north, of the straight
+    /// grid line to another point of the same zone.
+    ///
+    /// Returned in the interval [0..360). Northings are compared relative to
+    /// the equator, so both points may lie in different hemispheres.
+    pub fn grid_azimuth(&self, other: &Utm) -> Result<f64, UtmError> {
+        if self.zone != other.zone {
+            return Err(UtmError::SpansMultipleZones(self.zone, other.zone));
+        }
+
+        let de = other.easting - self.easting;
+        let dn = other.northing_signed() - self.northing_signed();
+
+        Ok(de.atan2(dn).to_degrees().rem_euclid(360.0))
+    }
+
+    /// Convert a bearing from true north to grid north at this point.
+    ///
+    /// The meridian convergence is computed with WGS84 and subtracted from
+    /// `true_bearing`. Both bearings are in degrees clockwise and the result
+    /// is returned in the interval [0..360).
+    pub fn true_to_grid_bearing(&self, true_bearing: f64) -> f64 {
+        (true_bearing - self.convergence(Datum::wgs84_ref())).rem_euclid(360.0)
+    }
+
+    /// Map-margin text block relating grid, true and magnetic north.
+    ///
+    /// The convergence is computed from the point with WGS84. The magnetic
+    /// declination is positive east and refers to the given `year`. Angles are
+    /// rounded to one decimal place and labelled east or west.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geomorph::*;
+    ///
+    /// let utm = utm::Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+    /// println!("{}", utm.declination_diagram_text(-22.5, 2019));
+    /// ```
+    pub fn declination_diagram_text(&self, magnetic_declination_deg: f64, year: i32) -> String {
+        let convergence = self.convergence(Datum::wgs84_ref());
+        let grid_magnetic = magnetic_declination_deg - convergence;
+
+        format!(
+            "Grid convergence: {}\nMagnetic declination ({}): {}\nGN-MN angle: {}",
+            east_west(convergence),
+            year,
+            east_west(magnetic_declination_deg),
+            east_west(grid_magnetic)
+        )
+    }
+}
+
+/// Central meridian of the Irish Transverse Mercator, in degrees
+const ITM_LON_0: f64 = -8.0;
+/// Latitude of the Irish Transverse Mercator origin, in degrees
+const ITM_LAT_0: f64 = 53.5;
+/// Scale factor on the Irish Transverse Mercator central meridian
+const ITM_K0: f64 = 0.99982;
+/// False easting of the Irish Transverse Mercator, in meters
+const ITM_FALSE_EASTING: f64 = 600000.0;
+/// False northing of the Irish Transverse Mercator, in meters
+const ITM_FALSE_NORTHING: f64 = 750000.0;
+
+///
+/// Irish Transverse Mercator (ITM, EPSG:2157) coordinates
+///
+/// ITM is a single transverse mercator zone on the GRS80 ellipsoid, centred
+/// on 8°W with its origin at 53.5°N. Coordinates are taken as ETRS89, which
+/// agrees with WGS84 to well under a meter.
+///
+/// # Example
+/// ```
+/// use geomorph::*;
+///
+/// let dublin = coord::Coord::new(53.349804, -6.260310);
+/// let itm = utm::IrishGrid::from_coord(&dublin);
+/// assert_eq!(itm.easting.round(), 715826.0);
+/// ```
+///
+#[derive(Debug, Clone, Copy)]
+pub struct IrishGrid {
+    pub easting: f64,
+    pub northing: f64,
+}
+
+impl IrishGrid {
+    /// Project a coordinate to ITM.
+    pub fn from_coord(coord: &Coord) -> IrishGrid {
+        let datum = itm_datum();
+        let (_, y0, _, _) = forward(&datum, ITM_LON_0, ITM_LAT_0, ITM_LON_0);
+        let (x, y, _, _) = forward(&datum, ITM_LON_0, coord.lat, coord.lon);
+
+        IrishGrid {
+            easting: x + ITM_FALSE_EASTING,
+            northing: y - y0 + ITM_FALSE_NORTHING,
+        }
+    }
+
+    /// Geographic coordinate of this ITM point.
+    pub fn to_coord(&self) -> Coord {
+        let datum = itm_datum();
+        let (_, y0, _, _) = forward(&datum, ITM_LON_0, ITM_LAT_0, ITM_LON_0);
+        let (lat, lon, _, _) = coord::inverse(
+            &datum,
+            ITM_LON_0,
+            self.easting - ITM_FALSE_EASTING,
+            self.northing - ITM_FALSE_NORTHING + y0,
+        );
+
+        Coord::new(lat, lon)
+    }
+}
+
+/// Transverse mercator parameters of the Irish grid
+fn itm_datum() -> Datum {
+    let (a, f) = datum::GRS80_ELLIPSOID;
+    Datum::transverse_mercator(a, f, ITM_K0)
+}
+
+///
+/// Regular grid of UTM points covering a bounding box
+///
+/// Both corners are projected to UTM and the grid starts at the south-west
+/// corner, stepping easting and northing by `spacing_m` meters up to the
+/// north-east corner. Northing is stepped relative to the equator, so boxes
+/// crossing it produce a continuous grid.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let sw = coord::Coord::new(-23.02, -43.45);
+/// let ne = coord::Coord::new(-23.00, -43.43);
+/// let grid = utm::grid_in_box(&sw, &ne, 1000.0).unwrap();
+/// ```
+///
+pub fn grid_in_box(sw: &Coord, ne: &Coord, spacing_m: f64) -> Result<Vec<Utm>, UtmError> {
+    if spacing_m <= 0.0 || !spacing_m.is_finite() {
+        return Err(UtmError::InvalidSpacing(spacing_m));
+    }
+
+    let sw: Utm = (*sw).into();
+    let ne: Utm = (*ne).into();
+    if sw.zone != ne.zone {
+        return Err(UtmError::SpansMultipleZones(sw.zone, ne.zone));
+    }
+
+    let datum = Datum::wgs84_ref();
+    let lon_0 = central_meridian(sw.zone);
+    let columns = ((ne.easting - sw.easting) / spacing_m).floor() + 1.0;
+    let rows = ((ne.northing_signed() - sw.northing_signed()) / spacing_m).floor() + 1.0;
+
+    let mut grid = Vec::with_capacity((columns.max(0.0) * rows.max(0.0)) as usize);
+    for row in 0..rows.max(0.0) as usize {
+        let northing = sw.northing_signed() + row as f64 * spacing_m;
+        for column in 0..columns.max(0.0) as usize {
+            let easting = sw.easting + column as f64 * spacing_m;
+            let mut utm = Utm::from_signed_northing(easting, northing, sw.zone, sw.band);
+            let (lat, _, _, _) = coord::reverse(&utm, datum, lon_0);
+            utm.band = lat_to_band(lat);
+            grid.push(utm);
+        }
+    }
+
+    Ok(grid)
+}
+
+///
+/// Ratio between the point scale factors at two latitudes of a UTM zone
+///
+/// Along the central meridian the scale factor is constant and equal to
+/// `k0`, so both points are taken on the zone edge, 3° east of the central
+/// meridian, where the distortion is largest. The returned value is the
+/// scale at `lat1` divided by the scale at `lat2`, computed with WGS84.
+///
+/// # Panics
+///
+/// Panics if `zone` is not contained in the interval [1..60]
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let ratio = utm::scale_ratio(23, 0.0, 56.0);
+/// assert!(ratio > 1.0);
+/// ```
+///
+pub fn scale_ratio(zone: i32, lat1: f64, lat2: f64) -> f64 {
+    assert!((1..=60).contains(&zone), "invalid UTM zone: {}", zone);
+
+    let datum = Datum::wgs84_ref();
+    let lon_0 = central_meridian(zone);
+    let (_, _, _, k1) = forward(datum, lon_0, lat1, lon_0 + 3.0);
+    let (_, _, _, k2) = forward(datum, lon_0, lat2, lon_0 + 3.0);
+
+    k1 / k2
+}
+
+///
+/// Transverse mercator projection in 3° wide zones
+///
+/// Zones are numbered 1 to 120 eastward and zone `n` is centred on the
+/// meridian `3n - 180`, so zone 60 is centred on Greenwich and zone 120 on
+/// the antimeridian. Each zone covers ±1.5° around its central meridian. The
+/// UTM projection parameters are kept: WGS84, scale factor 0.9996 and false
+/// easting 500,000 m.
+///
+/// The returned `zone` is the 3° zone number, so the result should not be
+/// converted back with the 6° UTM conversions.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let coord = coord::Coord::new(-23.0095839, -43.4361816);
+/// let utm = utm::from_coord_3deg(&coord);
+/// assert_eq!(utm.zone, 46);
+/// ```
+///
+pub fn from_coord_3deg(coord: &Coord) -> Utm {
+    let zone = ((coord.lon + 180.0) / 3.0).round() as i32;
+    let zone = (zone + 119) % 120 + 1;
+    let lon_0 = 3.0 * zone as f64 - 180.0;
+
+    project_to(Datum::wgs84_ref(), coord, zone, lon_0)
+}
+
+///
+/// Geographic position of the false origin of a UTM zone
+///
+/// The origin is the point at easting 500,000 m and at the false northing
+/// of the hemisphere, 0 m in the north and 10,000,000 m in the south. Both
+/// lie where the equator crosses the zone central meridian.
+///
+/// # Panics
+///
+/// Panics if `zone` is not contained in the interval [1..60]
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let origin = utm::zone_origin(23, true);
+/// assert_eq!(origin.lon.round(), -45.0);
+/// ```
+///
+pub fn zone_origin(zone: i32, north: bool) -> Coord {
+    assert!((1..=60).contains(&zone), "invalid UTM zone: {}", zone);
+
+    let (northing, band) = if north {
+        (0.0, 'N')
+    } else {
+        (SOUTH_FALSE_NORTHING, 'M')
+    };
+
+    Utm::new(FALSE_EASTING, northing, north, zone, band, false).into()
+}
+
+///
+/// Largest departure from unity of the point scale factor over a polygon
+///
+/// The scale factor is evaluated with WGS84 at every vertex of `ring` and at
+/// the midpoint of every edge, all projected into `zone`. An empty ring
+/// returns 0.
+///
+/// # Panics
+///
+/// Panics if `zone` is not contained in the interval [1..60]
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let ring = [
+///     coord::Coord::new(-24.0, -47.0),
+///     coord::Coord::new(-24.0, -43.0),
+///     coord::Coord::new(-22.0, -43.0),
+///     coord::Coord::new(-22.0, -47.0),
+/// ];
+/// let error = utm::max_scale_error(&ring, 23);
+/// assert!(error > 0.0004);
+/// ```
+///
+pub fn max_scale_error(ring: &[Coord], zone: i32) -> f64 {
+    assert!((1..=60).contains(&zone), "invalid UTM zone: {}", zone);
+
+    let datum = Datum::wgs84_ref();
+    let lon_0 = central_meridian(zone);
+    let error = |coord: &Coord| {
+        let (_, _, _, k) = forward(datum, lon_0, coord.lat, coord.lon);
+        (k - 1.0).abs()
+    };
+
+    let midpoints = ring
+        .iter()
+        .zip(ring.iter().cycle().skip(1))
+        .filter_map(|(a, b)| {
+            let (va, vb) = (a.to_vector(), b.to_vector());
+            Coord::from_vector([va[0] + vb[0], va[1] + vb[1], va[2] + vb[2]])
+        });
+
+    ring.iter()
+        .map(error)
+        .chain(midpoints.map(|coord| error(&coord)))
+        .fold(0.0, f64::max)
+}
+
+///
+/// UTM zone number of a coordinate
+///
+/// Includes the Norway and Svalbard exceptions. Coordinates outside the
+/// UTM latitude range [-80..84), covered by UPS, return 0.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// assert_eq!(utm::zone_for(&coord::Coord::new(-23.0095839, -43.4361816)), 23);
+/// assert_eq!(utm::zone_for(&coord::Coord::new(60.0, 5.0)), 32);
+/// ```
+///
+pub fn zone_for(coord: &Coord) -> i32 {
+    zone_from_coord(coord.lat, coord.lon)
+}
+
+///
+/// UTM zone number of a latitude and longitude in degrees
+///
+/// Same as [`zone_for`], without building a `Coord`: includes the Norway
+/// and Svalbard exceptions and returns 0 outside the UTM latitude range
+/// [-80..84).
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// assert_eq!(utm::zone_from_coord(60.0, 5.0), 32);
+/// assert_eq!(utm::zone_from_coord(78.0, 10.0), 33);
+/// ```
+///
+pub fn zone_from_coord(lat: f64, lon: f64) -> i32 {
+    if !(-80.0..84.0).contains(&lat) {
+        return 0;
+    }
+
+    let fmod_lon: f64 = math::fmod(lon, 360.0);
+    let ilon: f64 = if fmod_lon >= 180.0 {
+        fmod_lon - 360.0
+    } else if fmod_lon < -180.0 {
+        fmod_lon + 360.0
+    } else {
+        fmod_lon
+    };
+
+    let zone = ((ilon + 186.0) / 6.0).trunc() as i32;
+
+    let except_band: f64 = ((lat.floor() + 80.0) / 8.0 - 10.0)
+        .trunc()
+        .clamp(-10.0, 9.0);
+
+    if except_band == 7.0 && zone == 31 && ilon >= 3.0 {
+        // Norway UTM exception
+        32
+    } else if except_band == 9.0 && (0.0..=42.0).contains(&ilon) {
+        // Svalbard UTM exception
+        2 * (((ilon as i32) + 183) / 12) + 1
+    } else {
+        zone
+    }
+}
+
+///
+/// Sorted UTM zones touched by a polygon
+///
+/// The edges are densified every 10 km with [`coord::densify`] so that
+/// zones crossed between vertices are found, and [`zone_for`] is evaluated
+/// at every point. The ring is closed back to its first vertex if needed.
+/// Points in the polar UPS regions are ignored.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let ring = [
+///     coord::Coord::new(-24.0, -46.0),
+///     coord::Coord::new(-24.0, -40.0),
+///     coord::Coord::new(-22.0, -40.0),
+///     coord::Coord::new(-22.0, -46.0),
+/// ];
+/// assert_eq!(utm::zones_for_polygon(&ring), vec![23, 24]);
+/// ```
+///
+pub fn zones_for_polygon(ring: &[Coord]) -> Vec<i32> {
+    let mut closed = ring.to_vec();
+    if let (Some(&first), Some(last)) = (ring.first(), ring.last()) {
+        if first.lat != last.lat || first.lon != last.lon {
+            closed.push(first);
+        }
+    }
+
+    let mut zones: Vec<i32> = coord::densify(&closed, 10000.0)
+        .iter()
+        .map(zone_for)
+        .filter(|&zone| zone != 0)
+        .collect();
+    zones.sort_unstable();
+    zones.dedup();
+
+    zones
+}
+
+///
+/// Longitude interval, in degrees, between UTM grid lines `easting_spacing_m`
+/// meters apart at a given latitude
+///
+/// The interval is measured on the central meridian of `zone`, going east
+/// along the grid line of constant northing through `lat`. It includes the
+/// 0.9996 scale factor on the central meridian and widens slightly towards
+/// the zone edges.
+///
+/// # Panics
+///
+/// Panics if `zone` is not between 1 and 60
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let spacing = utm::grid_line_lon_spacing(100000.0, 0.0, 23);
+/// assert!((spacing - 0.9).abs() < 0.01);
+/// ```
+///
+pub fn grid_line_lon_spacing(easting_spacing_m: f64, lat: f64, zone: i32) -> f64 {
+    assert!((1..=60).contains(&zone), "invalid UTM zone: {}", zone);
+
+    let datum = Datum::wgs84_ref();
+    let lon_0 = central_meridian(zone);
+    let (_, y, _, _) = forward(datum, lon_0, lat, lon_0);
+    let (_, lon, _, _) = coord::inverse(datum, lon_0, easting_spacing_m, y);
+
+    lon - lon_0
+}
+
+///
+/// Convert a slice of coordinates to UTM
+///
+/// Equivalent to converting each point with `into`.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let coords = [coord::Coord::new(-23.0095839, -43.4361816), coord::Coord::new(52.52, 13.40)];
+/// let utms = utm::coords_to_utm(&coords);
+/// assert_eq!(utms[1].zone, 33);
+/// ```
+///
+pub fn coords_to_utm(coords: &[Coord]) -> Vec<Utm> {
+    let datum = Datum::wgs84_ref();
+    coords
+        .iter()
+        .map(|coord| from_coord(coord, datum))
+        .collect()
+}
+
+///
+/// Convert a slice of UTM coordinates to latitude and longitude
+///
+/// Equivalent to converting each point with `into`, with the WGS84 datum
+/// borrowed once for the whole slice.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// let utms = [utm::Utm::new(660265.0, 7454564.0, false, 23, 'K', false)];
+/// let coords = utm::utms_to_coords(&utms);
+/// assert!((coords[0].lat + 23.0095839).abs() < 1e-5);
+/// ```
+///
+pub fn utms_to_coords(utms: &[Utm]) -> Vec<Coord> {
+    let datum = Datum::wgs84_ref();
+    utms.iter()
+        .map(|utm| Coord::from_utm_with_datum(utm, datum))
+        .collect()
+}
+
+/// Transverse mercator projection around the central meridian `lon_0`
+/// returning easting and northing without false origin, meridian
+/// convergence (degrees) and point scale factor
+pub(crate) fn forward(datum: &Datum, lon_0: f64, lat: f64, lon: f64) -> (f64, f64, f64, f64) {
+    let mut lon_norm: f64 = math::angle_diff(lon_0, lon);
+
+    let mut latsign: f64 = if lat < 0.0 { -1.0 } else { 1.0 };
+    let lonsign: f64 = if lon_norm < 0.0 { -1.0 } else { 1.0 };
+
+    let lat_norm: f64 = lat * latsign;
+    lon_norm *= lonsign;
+
+    let backside: bool = lon_norm > 90.0;
+
+    if backside {
+        if lat_norm == 0.0 {
+            latsign = -1.0;
+        }
+        lon_norm = 180.0 - lon_norm;
+    }
+
+    let rlat: f64 = lat_norm.to_radians();
+    let rlon: f64 = lon_norm.to_radians();
+
+    let (sphi, cphi) = rlat.sin_cos();
+    let (slam, clam) = rlon.sin_cos();
+
+    let etap: f64;
+    let xip: f64;
+    let gamma: f64;
+    let k: f64;
+    if lat_norm != 90.0 {
+        let tau: f64 = sphi / cphi;
+        let taup: f64 = math::taupf(tau, datum.es);
+
+        xip = taup.atan2(clam);
+        etap = (slam / taup.hypot(clam)).asinh();
+        gamma = (slam * taup).atan2(clam * 1.0_f64.hypot(taup)).to_degrees();
+        k = (datum.e2m + datum.e2 * cphi.powi(2)).sqrt() * 1.0_f64.hypot(tau) / taup.hypot(clam);
+    } else {
+        xip = consts::PI / 2.0;
+        etap = 0.0;
+        gamma = lon_norm;
+        k = datum.c;
+    }
+
+    let c0: f64 = (2.0 * xip).cos();
+    let ch0: f64 = (2.0 * etap).cosh();
+    let s0: f64 = (2.0 * xip).sin();
+    let sh0: f64 = (2.0 * etap).sinh();
+
+    let mut a: Complex64 = Complex::new(2.0 * c0 * ch0, -2.0 * s0 * sh0);
+
+    let mut n = datum.maxpow;
+    let mut y0: Complex64 = Complex::new(0.0, 0.0);
+    let mut y1: Complex64 = Complex::new(0.0, 0.0);
+    let mut z0: Complex64 = Complex::new(0.0, 0.0);
+    let mut z1: Complex64 = Complex::new(0.0, 0.0);
+
+    while n > 0 {
+        y1 = (a * y0) - (y1) + (datum.alp[n]);
+        z1 = (a * z0) - (z1) + (2.0 * (n as f64) * datum.alp[n]);
+        n -= 1;
+        y0 = (a * y1) - (y0) + (datum.alp[n]);
+        z0 = (a * z1) - (z0) + (2.0 * (n as f64) * datum.alp[n]);
+        n -= 1;
+    }
+
+    a /= 2.0;
+    z1 = 1.0 - z1 + a * z0;
+    a = Complex::new(s0 * ch0, c0 * sh0);
+    y1 = Complex::new(xip, etap) + a * y0;
+
+    let xi: f64 = y1.re;
+    let eta: f64 = y1.im;
+
+    let mut gamma = gamma - z1.im.atan2(z1.re).to_degrees();
+    let k = k * datum.b1 * z1.norm();
+
+    if backside {
+        gamma = 180.0 - gamma;
+    }
+    gamma = math::angle_normalize(gamma * latsign * lonsign);
+
+    let y = datum.a1 * datum.k0 * (if backside { consts::PI - xi } else { xi }) * latsign;
+    let x = datum.a1 * datum.k0 * eta * lonsign;
+
+    (x, y, gamma, k * datum.k0)
+}
+
+/// Central scale factor of the UPS projections
+pub(crate) const UPS_K0: f64 = 0.994;
+
+/// Polar stereographic projection centred on the north or south pole
+/// returning easting and northing without false origin, meridian
+/// convergence (degrees) and point scale factor
+pub(crate) fn polar_forward(
+    datum: &Datum,
+    north: bool,
+    lat: f64,
+    lon: f64,
+) -> (f64, f64, f64, f64) {
+    let lat = if north { lat } else { -lat };
+    let tau = lat.to_radians().tan();
+    let secphi = 1.0_f64.hypot(tau);
+    let taup = math::taupf(tau, datum.es);
+
+    let rho = 1.0_f64.hypot(taup) + taup.abs();
+    let rho = if taup < 0.0 {
+        rho
+    } else if lat != 90.0 {
+        1.0 / rho
+    } else {
+        0.0
+    };
+    let rho = rho * 2.0 * UPS_K0 * datum.a / datum.c;
+
+    let k = if lat != 90.0 {
+        rho / datum.a * secphi * (datum.e2m + datum.e2 / secphi.powi(2)).sqrt()
+    } else {
+        UPS_K0
+    };
+
+    let (slam, clam) = lon.to_radians().sin_cos();
+    let x = rho * slam;
+    let y = if north { -rho * clam } else { rho * clam };
+    let gamma = math::angle_normalize(if north { lon } else { -lon });
+
+    (x, y, gamma, k)
+}
+
+/// UPS band letter: A or B in the south and Y or Z in the north, splitting
+/// the polar caps along the 0/180 meridians
+fn ups_band(north: bool, easting: f64) -> char {
+    match (north, easting >= UPS_FALSE_ORIGIN) {
+        (false, false) => 'A',
+        (false, true) => 'B',
+        (true, false) => 'Y',
+        (true, true) => 'Z',
+    }
+}
+
+///
+/// UTM latitude band letter of a latitude in degrees
+///
+/// Bands are 8° tall from C, starting at 80°S, to X, which is 12° tall and
+/// ends at 84°N. Latitudes further south are labelled C and further north X;
+/// the polar UPS bands depend on the longitude as well and are not covered.
+///
+/// # Example
+///
+/// ```
+/// use geomorph::*;
+///
+/// assert_eq!(utm::lat_to_band(-23.0095839), 'K');
+/// assert_eq!(utm::lat_to_band(0.0), 'N');
+/// ```
+///
+pub fn lat_to_band(lat: f64) -> char {
+    if lat < -72.0 {
+        'C'
+    } else if lat < -64.0 {
+        'D'
+    } else if lat < -56.0 {
+        'E'
+    } else if lat < -48.0 {
+        'F'
+    } else if lat < -40.0 {
+        'G'
+    } else if lat < -32.0 {
+        'H'
+    } else if lat < -24.0 {
+        'J'
+    } else if lat < -16.0 {
+        'K'
+    } else if lat < -8.0 {
+        'L'
+    } else if lat < 0.0 {
+        'M'
+    } else if lat < 8.0 {
+        'N'
+    } else if lat < 16.0 {
+        'P'
+    } else if lat < 24.0 {
+        'Q'
+    } else if lat < 32.0 {
+        'R'
+    } else if lat < 40.0 {
+        'S'
+    } else if lat < 48.0 {
+        'T'
+    } else if lat < 56.0 {
+        'U'
+    } else if lat < 64.0 {
+        'V'
+    } else if lat < 72.0 {
+        'W'
+    } else {
+        'X'
+    }
+}
+
+/// False easting, in meters, of the UTM zones
+const FALSE_EASTING: f64 = 500000.0;
+
+/// False northing, in meters, of the southern hemisphere UTM zones
+pub(crate) const SOUTH_FALSE_NORTHING: f64 = 10000000.0;
+
+/// False easting and northing, in meters, of the UPS projections
+const UPS_FALSE_ORIGIN: f64 = 2000000.0;
+
+/// Project a coordinate around `lon_0`, labelling the result with `zone`
+/// regardless of the zone the coordinate naturally falls in
+pub(crate) fn project_to(datum: &Datum, coord: &Coord, zone: i32, lon_0: f64) -> Utm {
+    let (x, y, _, _) = forward(datum, lon_0, coord.lat, coord.lon);
+    Utm::from_signed_northing(x + FALSE_EASTING, y, zone, lat_to_band(coord.lat))
+}
+
+/// Central meridian, in degrees, of a given UTM zone
+pub(crate) fn central_meridian(zone: i32) -> f64 {
+    6.0 * (zone as f64) - 183.0
+}
+
+/// Format an angle in degrees as its absolute value followed by E or W
+fn east_west(angle: f64) -> String {
+    let angle = (angle * 10.0).round() / 10.0;
+    format!(
+        "{:.1}\u{b0} {}",
+        angle.abs(),
+        if angle < 0.0 { 'W' } else { 'E' }
+    )
 }
 
 impl fmt::Display for Utm {
@@ -54,192 +961,66 @@ impl From<Mgrs> for Utm {
 
 impl From<Coord> for Utm {
     fn from(coord: Coord) -> Self {
-        let lat = coord.lat;
-        let lon = coord.lon;
+        Utm::project(&coord, Datum::wgs84_ref())
+    }
+}
 
-        let datum = Datum::wgs84();
+impl From<&Mgrs> for Utm {
+    fn from(mgrs: &Mgrs) -> Self {
+        mgrs.utm
+    }
+}
 
-        let utm_exceptions: bool = true;
-
-        let easting: f64;
-        let northing: f64;
-        let north: bool;
-        let mut zone: i32;
-        let band: char;
-        let ups: bool;
-
-        if lat < -72.0 {
-            band = 'C';
-        } else if lat < -64.0 {
-            band = 'D';
-        } else if lat < -56.0 {
-            band = 'E';
-        } else if lat < -48.0 {
-            band = 'F';
-        } else if lat < -40.0 {
-            band = 'G';
-        } else if lat < -32.0 {
-            band = 'H';
-        } else if lat < -24.0 {
-            band = 'J';
-        } else if lat < -16.0 {
-            band = 'K';
-        } else if lat < -8.0 {
-            band = 'L';
-        } else if lat < 0.0 {
-            band = 'M';
-        } else if lat < 8.0 {
-            band = 'N';
-        } else if lat < 16.0 {
-            band = 'P';
-        } else if lat < 24.0 {
-            band = 'Q';
-        } else if lat < 32.0 {
-            band = 'R';
-        } else if lat < 40.0 {
-            band = 'S';
-        } else if lat < 48.0 {
-            band = 'T';
-        } else if lat < 56.0 {
-            band = 'U';
-        } else if lat < 64.0 {
-            band = 'V';
-        } else if lat < 72.0 {
-            band = 'W';
-        } else {
-            band = 'X';
-        }
+impl From<&Coord> for Utm {
+    fn from(coord: &Coord) -> Self {
+        Utm::project(coord, Datum::wgs84_ref())
+    }
+}
 
-        north = lat >= 0.0;
-        ups = lat < -80.0 || lat >= 84.0;
-
-        if !ups {
-            let fmod_lon: f64 = math::fmod(lon, 360.0);
-            let ilon: f64;
-            if fmod_lon >= 180.0 {
-                ilon = fmod_lon - 360.0;
-            } else if fmod_lon < -180.0 {
-                ilon = fmod_lon + 360.0;
-            } else {
-                ilon = fmod_lon;
-            }
-
-            zone = ((ilon + 186.0) / 6.0).trunc() as i32;
-
-            let except_band: f64 = ((lat.floor() + 80.0) / 8.0 - 10.0)
-                .trunc()
-                .min(9.0)
-                .max(-10.0);
-
-            if utm_exceptions {
-                if except_band == 7.0 && zone == 31 && ilon >= 3.0 {
-                    // Norway UTM exception
-                    zone = 32;
-                } else if except_band == 9.0 && ilon >= 0.0 && ilon <= 42.0 {
-                    // Svalbard UTM exception
-                    zone = 2 * (((ilon as i32) + 183) / 12) + 1;
-                }
-            }
-        } else {
-            zone = 0;
-        }
+/// UTM, or UPS at polar latitudes, in the zone of each coordinate
+impl Projection for Utm {
+    fn project(coord: &Coord, datum: &Datum) -> Self {
+        from_coord(coord, datum)
+    }
 
-        if !ups {
-            let lon_0: f64 = 6.0 * (zone as f64) - 183.0;
-            let mut lon_norm: f64 = math::angle_diff(lon_0, lon);
-
-            let mut latsign: f64;
-            if lat < 0.0 {
-                latsign = -1.0
-            } else {
-                latsign = 1.0
-            }
-            let lonsign: f64;
-            if lon_norm < 0.0 {
-                lonsign = -1.0
-            } else {
-                lonsign = 1.0
-            }
-
-            let lat_norm: f64 = lat * latsign;
-            lon_norm = lon_norm * lonsign;
-
-            let backside: bool = lon_norm > 90.0;
-
-            if backside {
-                if lat_norm == 0.0 {
-                    latsign = -1.0;
-                }
-                lon_norm = 180.0 - lon_norm;
-            }
-
-            let rlat: f64 = lat_norm.to_radians();
-            let rlon: f64 = lon_norm.to_radians();
-
-            let (sphi, cphi) = rlat.sin_cos();
-            let (slam, clam) = rlon.sin_cos();
-
-            let etap: f64;
-            let xip: f64;
-            if lat_norm != 90.0 {
-                let tau: f64 = sphi / cphi;
-                let taup: f64 = math::taupf(tau, datum.es);
-
-                xip = taup.atan2(clam);
-                etap = (slam / taup.hypot(clam)).asinh();
-            } else {
-                xip = consts::PI / 2.0;
-                etap = 0.0;
-            }
-
-            let c0: f64 = (2.0 * xip).cos();
-            let ch0: f64 = (2.0 * etap).cosh();
-            let s0: f64 = (2.0 * xip).sin();
-            let sh0: f64 = (2.0 * etap).sinh();
-
-            let mut a: Complex64 = Complex::new(2.0 * c0 * ch0, -2.0 * s0 * sh0);
-
-            let mut n = datum.maxpow;
-            let mut y0: Complex64 = Complex::new(0.0, 0.0);
-            let mut y1: Complex64 = Complex::new(0.0, 0.0);
-            let mut z0: Complex64 = Complex::new(0.0, 0.0);
-            let mut z1: Complex64 = Complex::new(0.0, 0.0);
-
-            while n > 0 {
-                y1 = (a * y0) - (y1) + (datum.alp[n]);
-                z1 = (a * z0) - (z1) + (2.0 * (n as f64) * datum.alp[n]);
-                n = n - 1;
-                y0 = (a * y1) - (y0) + (datum.alp[n]);
-                z0 = (a * z1) - (z0) + (2.0 * (n as f64) * datum.alp[n]);
-                n = n - 1;
-            }
-
-            a = Complex::new(s0 * ch0, c0 * sh0);
-            y1 = Complex::new(xip, etap) + a * y0;
-
-            let xi: f64 = y1.re;
-            let eta: f64 = y1.im;
-
-            let ind: usize = if ups { 0 } else { 2 } + if north { 1 } else { 0 };
-
-            northing =
-                datum.a1 * datum.k0 * (if backside { consts::PI - xi } else { xi }) * latsign
-                    + datum.false_northing[ind];
-            easting = datum.a1 * datum.k0 * eta * lonsign + datum.false_easting[ind];
-        } else {
-            easting = 0.0;
-            northing = 0.0;
-            zone = 0;
-        }
+    fn unproject(&self, datum: &Datum) -> Coord {
+        Coord::from_utm_with_datum(self, datum)
+    }
+}
 
-        Utm {
-            easting,
-            northing,
-            north,
-            zone,
-            band,
-            ups,
-        }
+/// Project a coordinate into its UTM zone with a prebuilt datum
+pub(crate) fn from_coord(coord: &Coord, datum: &Datum) -> Utm {
+    let lat = coord.lat;
+    let lon = coord.lon;
+
+    let zone: i32 = zone_for(coord);
+
+    let north: bool = lat >= 0.0;
+    let ups: bool = zone == 0;
+
+    let (x, y, _, _) = if !ups {
+        forward(datum, central_meridian(zone), lat, lon)
+    } else {
+        polar_forward(datum, north, lat, lon)
+    };
+
+    let ind: usize = if ups { 0 } else { 2 } + if north { 1 } else { 0 };
+
+    let northing = y + datum.false_northing[ind];
+    let easting = x + datum.false_easting[ind];
+    let band: char = if ups {
+        ups_band(north, easting)
+    } else {
+        lat_to_band(lat)
+    };
+
+    Utm {
+        easting,
+        northing,
+        north,
+        zone,
+        band,
+        ups,
     }
 }
 
@@ -256,11 +1037,81 @@ mod tests {
         let utm: Utm = coord.into();
         assert_eq!(utm.easting.trunc(), 660265.0);
         assert_eq!(utm.northing.trunc(), 7454564.0);
-        assert_eq!(utm.north, false);
+        assert!(!utm.north);
         assert_eq!(utm.zone, 23);
         assert_eq!(utm.band, 'K');
     }
 
+    #[test]
+    fn from_references() {
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        let utm = Utm::from(&coord);
+        assert_eq!(utm.zone, 23);
+        assert_eq!(utm.easting.trunc(), 660265.0);
+        assert_eq!(coord.lat, -23.0095839);
+
+        let mgrs: Mgrs = (&utm).into();
+        let from_mgrs = Utm::from(&mgrs);
+        assert_eq!(from_mgrs.easting, utm.easting);
+        assert_eq!(mgrs.utm.northing, utm.northing);
+    }
+
+    #[test]
+    fn ups_zone_north() {
+        // Reference values from the Snyder (1987) polar stereographic
+        // formulas with the UPS scale factor 0.994
+        let cases = [
+            (87.0, 30.0, 2166572.2427, 1711488.4125, 'Z'),
+            (84.0, -120.0, 1422596.8712, 2333363.8518, 'Y'),
+            (90.0, 0.0, 2000000.0, 2000000.0, 'Z'),
+        ];
+        for &(lat, lon, easting, northing, band) in cases.iter() {
+            let utm: Utm = Coord::new(lat, lon).into();
+            assert!((utm.easting - easting).abs() < 1e-3);
+            assert!((utm.northing - northing).abs() < 1e-3);
+            assert!(utm.north);
+            assert!(utm.ups);
+            assert_eq!(utm.zone, 0);
+            assert_eq!(utm.band, band);
+        }
+    }
+
+    #[test]
+    fn ups_zone_south() {
+        let cases = [
+            (-87.0, 30.0, 2166572.2427, 2288511.5875, 'B'),
+            (-85.0, -60.0, 1518959.7883, 2277728.6957, 'A'),
+            (-80.5, 135.0, 2747448.3311, 1252551.6689, 'B'),
+        ];
+        for &(lat, lon, easting, northing, band) in cases.iter() {
+            let utm: Utm = Coord::new(lat, lon).into();
+            assert!((utm.easting - easting).abs() < 1e-3);
+            assert!((utm.northing - northing).abs() < 1e-3);
+            assert!(!utm.north);
+            assert!(utm.ups);
+            assert_eq!(utm.zone, 0);
+            assert_eq!(utm.band, band);
+        }
+
+        let (_, _, _, k) = polar_forward(&Datum::wgs84(), false, -90.0, 0.0);
+        assert_eq!(k, UPS_K0);
+    }
+
+    #[test]
+    fn from_coord_with_datum() {
+        let coord = Coord::new(51.4778, -0.0015);
+        let wgs84 = Utm::from_coord_with_datum(&coord, &Datum::wgs84());
+        let from: Utm = coord.into();
+        assert_eq!(wgs84.easting, from.easting);
+        assert_eq!(wgs84.northing, from.northing);
+
+        let airy = Datum::from_epsg_ellipsoid(7001).unwrap();
+        let utm = Utm::from_coord_with_datum(&coord, &airy);
+        assert_eq!((utm.zone, utm.band), (wgs84.zone, wgs84.band));
+        assert!((utm.easting - wgs84.easting).abs() > 1.0);
+        assert!((utm.northing - wgs84.northing).abs() > 1.0);
+    }
+
     #[test]
     fn utm_zone_north() {
         let coord = Coord {
@@ -270,7 +1121,7 @@ mod tests {
         let utm: Utm = coord.into();
         assert_eq!(utm.easting.trunc(), 392273.0);
         assert_eq!(utm.northing.trunc(), 5819744.0);
-        assert_eq!(utm.north, true);
+        assert!(utm.north);
         assert_eq!(utm.zone, 33);
         assert_eq!(utm.band, 'U');
     }
@@ -284,7 +1135,7 @@ mod tests {
         let utm: Utm = coord.into();
         assert_eq!(utm.easting.trunc(), 267038.0);
         assert_eq!(utm.northing.trunc(), 6779002.0);
-        assert_eq!(utm.north, true);
+        assert!(utm.north);
         assert_eq!(utm.zone, 32);
         assert_eq!(utm.band, 'V');
     }
@@ -298,7 +1149,7 @@ mod tests {
         let utm: Utm = coord.into();
         assert_eq!(utm.easting.trunc(), 402386.0);
         assert_eq!(utm.northing.trunc(), 8761675.0);
-        assert_eq!(utm.north, true);
+        assert!(utm.north);
         assert_eq!(utm.zone, 33);
         assert_eq!(utm.band, 'X');
     }
@@ -312,7 +1163,7 @@ mod tests {
         let utm: Utm = coord.into();
         assert_eq!(utm.easting.trunc(), 622751.0);
         assert_eq!(utm.northing.trunc(), 8677619.0);
-        assert_eq!(utm.north, true);
+        assert!(utm.north);
         assert_eq!(utm.zone, 33);
         assert_eq!(utm.band, 'X');
     }
@@ -326,7 +1177,7 @@ mod tests {
         let utm: Utm = coord.into();
         assert_eq!(utm.easting.trunc(), 362459.0);
         assert_eq!(utm.northing.trunc(), 8676854.0);
-        assert_eq!(utm.north, true);
+        assert!(utm.north);
         assert_eq!(utm.zone, 35);
         assert_eq!(utm.band, 'X');
     }
@@ -340,7 +1191,7 @@ mod tests {
         let utm: Utm = coord.into();
         assert_eq!(utm.easting.trunc(), 573272.0);
         assert_eq!(utm.northing.trunc(), 8675799.0);
-        assert_eq!(utm.north, true);
+        assert!(utm.north);
         assert_eq!(utm.zone, 35);
         assert_eq!(utm.band, 'X');
     }
@@ -353,7 +1204,7 @@ mod tests {
         let utm: Utm = coord.into();
         assert_eq!(utm.easting.trunc(), 273893.0);
         assert_eq!(utm.northing.trunc(), 6227030.0);
-        assert_eq!(utm.north, false);
+        assert!(!utm.north);
         assert_eq!(utm.zone, 34);
         assert_eq!(utm.band, 'H');
         assert_eq!(coord.lat, lat);
@@ -398,6 +1249,19 @@ mod tests {
         assert_eq!(utm.band, band);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let utm = Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+        let json = serde_json::to_string(&utm).unwrap();
+        assert_eq!(
+            json,
+            r#"{"easting":660265.0,"northing":7454564.0,"north":false,"zone":23,"band":"K","ups":false}"#
+        );
+        let decoded: Utm = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.to_bytes(), utm.to_bytes());
+    }
+
     #[test]
     fn utm_clone() {
         let easting: f64 = 298559.28045456996;
@@ -407,12 +1271,457 @@ mod tests {
         let band: char = 'N';
         let ups: bool = false;
         let mut utm_base = Utm::new(easting, northing, north, zone, band, ups);
-        let utm = utm_base.clone();
+        let utm = utm_base;
         utm_base.easting = 0.0;
+        assert_eq!(utm_base.easting, 0.0);
         assert_eq!(utm.easting, easting);
         assert_eq!(utm.northing, northing);
         assert_eq!(utm.north, north);
         assert_eq!(utm.zone, zone);
         assert_eq!(utm.band, band);
     }
+
+    #[test]
+    fn grid_in_box() {
+        let sw = Coord::new(-23.02, -43.45);
+        let ne = Coord::new(-23.00, -43.43);
+        let sw_utm: Utm = sw.into();
+        let ne_utm: Utm = ne.into();
+        let columns = ((ne_utm.easting - sw_utm.easting) / 1000.0).floor() as usize + 1;
+        let rows = ((ne_utm.northing - sw_utm.northing) / 1000.0).floor() as usize + 1;
+        assert_eq!(columns, 3);
+        assert_eq!(rows, 3);
+
+        let grid = super::grid_in_box(&sw, &ne, 1000.0).unwrap();
+        assert_eq!(grid.len(), columns * rows);
+        assert_eq!(grid[0].easting, sw_utm.easting);
+        assert_eq!(grid[0].northing, sw_utm.northing);
+        assert_eq!(grid[4].easting, sw_utm.easting + 1000.0);
+        assert_eq!(grid[4].northing, sw_utm.northing + 1000.0);
+        assert!(grid.iter().all(|utm| utm.zone == 23 && utm.band == 'K'));
+
+        let ne = Coord::new(-23.00, -41.0);
+        assert_eq!(
+            super::grid_in_box(&sw, &ne, 1000.0).unwrap_err(),
+            UtmError::SpansMultipleZones(23, 24)
+        );
+        assert_eq!(
+            super::grid_in_box(&sw, &sw, 0.0).unwrap_err(),
+            UtmError::InvalidSpacing(0.0)
+        );
+    }
+
+    #[test]
+    fn signed_northing() {
+        let utm = Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+        assert_eq!(utm.northing_signed(), -2545436.0);
+
+        let rebuilt = Utm::from_signed_northing(660265.0, -2545436.0, 23, 'K');
+        assert_eq!(rebuilt.northing, 7454564.0);
+        assert!(!rebuilt.north);
+
+        let utm = Utm::new(392273.0, 5819744.0, true, 33, 'U', false);
+        assert_eq!(utm.northing_signed(), 5819744.0);
+        assert!(Utm::from_signed_northing(392273.0, 5819744.0, 33, 'U').north);
+    }
+
+    #[test]
+    fn epsg_code() {
+        let rio: Utm = Coord::new(-23.0095839, -43.4361816).into();
+        assert_eq!(rio.epsg_code(), Some(32723));
+        let berlin: Utm = Coord::new(52.52, 13.40).into();
+        assert_eq!(berlin.epsg_code(), Some(32633));
+        let equator: Utm = Coord::new(0.0, -177.0).into();
+        assert_eq!(equator.epsg_code(), Some(32601));
+
+        let pole: Utm = Coord::new(90.0, 0.0).into();
+        assert_eq!(pole.epsg_code(), None);
+        let invalid = Utm::new(500000.0, 0.0, true, 61, 'N', false);
+        assert_eq!(invalid.epsg_code(), None);
+    }
+
+    #[test]
+    fn truncated() {
+        let utm = Utm::new(660265.7, 7454564.2, false, 23, 'K', false);
+        let truncated = utm.truncated(100.0);
+        assert_eq!(truncated.easting, 660200.0);
+        assert_eq!(truncated.northing, 7454500.0);
+        assert_eq!(truncated.zone, 23);
+        assert_eq!(truncated.band, 'K');
+        assert!(!truncated.north);
+    }
+
+    #[test]
+    #[should_panic]
+    fn truncated_invalid_resolution() {
+        Utm::new(660265.0, 7454564.0, false, 23, 'K', false).truncated(0.0);
+    }
+
+    #[test]
+    fn scale_ratio() {
+        let ratio = super::scale_ratio(23, 0.5, 55.5);
+        assert_eq!((ratio * 1e6).round(), 1000940.0);
+        assert_eq!(super::scale_ratio(23, 30.0, 30.0), 1.0);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            seed >> 11
+        };
+
+        for _ in 0..1000 {
+            let utm = Utm::new(
+                (next() % 1_000_000_000) as f64 / 1000.0,
+                (next() % 10_000_000_000) as f64 / 1000.0,
+                next() % 2 == 0,
+                (next() % 60) as i32 + 1,
+                "CDEFGHJKLMNPQRSTUVWX".as_bytes()[(next() % 20) as usize] as char,
+                next() % 2 == 0,
+            );
+            let decoded = Utm::from_bytes(&utm.to_bytes());
+            assert_eq!(decoded.easting.to_bits(), utm.easting.to_bits());
+            assert_eq!(decoded.northing.to_bits(), utm.northing.to_bits());
+            assert_eq!(decoded.north, utm.north);
+            assert_eq!(decoded.zone, utm.zone);
+            assert_eq!(decoded.band, utm.band);
+            assert_eq!(decoded.ups, utm.ups);
+        }
+    }
+
+    #[test]
+    fn from_coord_3deg() {
+        let coord = Coord::new(-23.0095839, -43.4361816);
+        let six: Utm = coord.into();
+        let three = super::from_coord_3deg(&coord);
+        assert_eq!(six.zone, 23);
+        assert_eq!(three.zone, 46);
+        assert_ne!(six.easting.round(), three.easting.round());
+        assert_eq!(three.band, 'K');
+        assert!(!three.north);
+
+        // Zone 46 is centred on -42
+        let on_meridian = super::from_coord_3deg(&Coord::new(-23.0, -42.0));
+        assert_eq!(on_meridian.zone, 46);
+        assert!((on_meridian.easting - 500000.0).abs() < 1e-6);
+
+        assert_eq!(super::from_coord_3deg(&Coord::new(0.0, 0.0)).zone, 60);
+        assert_eq!(super::from_coord_3deg(&Coord::new(0.0, 179.9)).zone, 120);
+        assert_eq!(super::from_coord_3deg(&Coord::new(0.0, -179.9)).zone, 120);
+        assert_eq!(super::from_coord_3deg(&Coord::new(0.0, -178.0)).zone, 1);
+    }
+
+    #[test]
+    fn zone_origin() {
+        let origin = super::zone_origin(23, true);
+        assert!(origin.lat.abs() < 1e-9);
+        assert!((origin.lon + 45.0).abs() < 1e-9);
+
+        let origin = super::zone_origin(31, false);
+        assert!(origin.lat.abs() < 1e-9);
+        assert!((origin.lon - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zone_origin_invalid_zone() {
+        super::zone_origin(0, true);
+    }
+
+    #[test]
+    fn max_scale_error() {
+        let ring = [
+            Coord::new(-24.0, -47.5),
+            Coord::new(-24.0, -42.5),
+            Coord::new(-22.0, -42.5),
+            Coord::new(-22.0, -47.5),
+        ];
+        let error = super::max_scale_error(&ring, 23);
+        assert!(error > 0.0004);
+        assert!(error < 0.001);
+
+        // The edges cross the central meridian, where the error is 1 - k0
+        let narrow = [Coord::new(-24.0, -45.1), Coord::new(-22.0, -44.9)];
+        assert!((super::max_scale_error(&narrow, 23) - 0.0004).abs() < 1e-5);
+
+        assert_eq!(super::max_scale_error(&[], 23), 0.0);
+    }
+
+    #[test]
+    fn zone_for() {
+        assert_eq!(super::zone_for(&Coord::new(-23.0, -43.4)), 23);
+        assert_eq!(super::zone_for(&Coord::new(0.0, -180.0)), 1);
+        assert_eq!(super::zone_for(&Coord::new(0.0, 179.9)), 60);
+        assert_eq!(super::zone_for(&Coord::new(60.0, 4.0)), 32);
+        assert_eq!(super::zone_for(&Coord::new(78.0, 10.0)), 33);
+        assert_eq!(super::zone_for(&Coord::new(85.0, 10.0)), 0);
+        assert_eq!(super::zone_for(&Coord::new(-81.0, 10.0)), 0);
+    }
+
+    #[test]
+    fn lat_to_band() {
+        let bands = "CDEFGHJKLMNPQRSTUVWX";
+        for (i, band) in bands.chars().enumerate() {
+            let south = -80.0 + 8.0 * i as f64;
+            assert_eq!(super::lat_to_band(south), band);
+            assert_eq!(super::lat_to_band(south + 7.999), band);
+        }
+        assert_eq!(super::lat_to_band(83.999), 'X');
+        assert_eq!(super::lat_to_band(-0.000001), 'M');
+    }
+
+    #[test]
+    fn zone_from_coord() {
+        assert_eq!(super::zone_from_coord(-23.0, -43.4), 23);
+        assert_eq!(super::zone_from_coord(0.0, 180.0), 1);
+        // Norway: 32V widened west to 3°E over band V only
+        assert_eq!(super::zone_from_coord(56.0, 3.0), 32);
+        assert_eq!(super::zone_from_coord(63.999, 2.999), 31);
+        assert_eq!(super::zone_from_coord(55.999, 3.0), 31);
+        assert_eq!(super::zone_from_coord(64.0, 3.0), 31);
+        // Svalbard: zones 31X, 33X, 35X and 37X only
+        assert_eq!(super::zone_from_coord(72.0, 8.999), 31);
+        assert_eq!(super::zone_from_coord(72.0, 9.0), 33);
+        assert_eq!(super::zone_from_coord(72.0, 21.0), 35);
+        assert_eq!(super::zone_from_coord(72.0, 33.0), 37);
+        assert_eq!(super::zone_from_coord(84.0, 10.0), 0);
+    }
+
+    #[test]
+    fn zones_for_polygon() {
+        let ring = [
+            Coord::new(-24.0, -44.0),
+            Coord::new(-24.0, -41.0),
+            Coord::new(-22.0, -41.0),
+            Coord::new(-22.0, -44.0),
+            Coord::new(-24.0, -44.0),
+        ];
+        assert_eq!(super::zones_for_polygon(&ring), vec![23, 24]);
+
+        // Zone 23 is only crossed between the vertices
+        let line = [Coord::new(0.0, -50.0), Coord::new(0.0, -37.0)];
+        assert_eq!(super::zones_for_polygon(&line), vec![22, 23, 24]);
+
+        // Open ring whose closing edge alone crosses zone 40, the other
+        // edges running through the polar region
+        let open = [
+            Coord::new(83.0, 50.0),
+            Coord::new(85.0, 50.0),
+            Coord::new(85.0, 62.0),
+            Coord::new(83.0, 62.0),
+        ];
+        assert_eq!(super::zones_for_polygon(&open), vec![39, 40, 41]);
+
+        assert!(super::zones_for_polygon(&[]).is_empty());
+    }
+
+    #[test]
+    fn grid_line_lon_spacing() {
+        let equator = super::grid_line_lon_spacing(100000.0, 0.0, 23);
+        let expected = (100000.0 / (0.9996 * 6378137.0_f64)).to_degrees();
+        assert!((equator - expected).abs() < 1e-3);
+
+        let lat: f64 = 60.0;
+        let north = super::grid_line_lon_spacing(100000.0, lat, 23);
+        let south = super::grid_line_lon_spacing(100000.0, -lat, 23);
+        assert!((north - south).abs() < 1e-9);
+        assert!((north * lat.to_radians().cos() - equator).abs() < 0.02);
+    }
+
+    #[test]
+    fn coords_to_utm() {
+        let coords = [
+            Coord::new(-23.0095839, -43.4361816),
+            Coord::new(52.52, 13.40),
+            Coord::new(61.0, 5.0),
+            Coord::new(-88.0, 120.0),
+            Coord::new(0.0, 179.9),
+        ];
+
+        let utms = super::coords_to_utm(&coords);
+        assert_eq!(utms.len(), coords.len());
+        for (coord, utm) in coords.iter().zip(utms.iter()) {
+            let expected: Utm = (*coord).into();
+            assert_eq!(utm.easting, expected.easting);
+            assert_eq!(utm.northing, expected.northing);
+            assert_eq!(utm.zone, expected.zone);
+            assert_eq!(utm.band, expected.band);
+        }
+
+        let back = super::utms_to_coords(&utms);
+        for (utm, coord) in utms.iter().zip(back.iter()) {
+            let expected: Coord = (*utm).into();
+            assert_eq!(coord.lat, expected.lat);
+            assert_eq!(coord.lon, expected.lon);
+        }
+
+        assert!(super::coords_to_utm(&[]).is_empty());
+        assert!(super::utms_to_coords(&[]).is_empty());
+    }
+
+    #[test]
+    fn irish_grid() {
+        // Reference values from the Ordnance Survey transverse mercator
+        // series with the ITM parameters
+        let dublin = Coord::new(53.349804, -6.260310);
+        let itm = IrishGrid::from_coord(&dublin);
+        assert!((itm.easting - 715825.830).abs() < 0.01);
+        assert!((itm.northing - 734698.021).abs() < 0.01);
+
+        let back = itm.to_coord();
+        assert!((back.lat - dublin.lat).abs() < 1e-9);
+        assert!((back.lon - dublin.lon).abs() < 1e-9);
+
+        let origin = IrishGrid::from_coord(&Coord::new(53.5, -8.0));
+        assert!((origin.easting - 600000.0).abs() < 1e-6);
+        assert!((origin.northing - 750000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn convergence() {
+        let utm = Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+        let gamma = utm.convergence(&Datum::wgs84());
+        assert_eq!((gamma * 100.0).round(), -61.0);
+
+        let utm = Utm::new(500000.0, 4000000.0, true, 33, 'S', false);
+        assert!(utm.convergence(&Datum::wgs84()).abs() < 1e-9);
+
+        // Reference values from the transverse mercator convergence series,
+        // at the zone edges and in the widened zone 32V
+        let cases = [
+            (45.0, 18.0, -2.1222997),
+            (-23.0, -42.0, 1.1731174),
+            (60.0, 10.5, 1.2991127),
+        ];
+        for &(lat, lon, expected) in cases.iter() {
+            let utm: Utm = Coord::new(lat, lon).into();
+            assert!((utm.convergence(&Datum::wgs84()) - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn scale_factor() {
+        let datum = Datum::wgs84();
+        let utm = Utm::new(500000.0, 4000000.0, true, 33, 'S', false);
+        assert!((utm.scale_factor(&datum) - 0.9996).abs() < 1e-12);
+
+        let utm: Utm = Coord::new(-23.0095839, -43.4361816).into();
+        let (_, k) = Coord::new(-23.0095839, -43.4361816).projection_factors(23);
+        assert!((utm.scale_factor(&datum) - k).abs() < 1e-12);
+
+        // Grows away from the central meridian, symmetrically
+        let mut previous = datum.k0;
+        for i in 1..=4 {
+            let offset = 100000.0 * i as f64;
+            let east = Utm::new(500000.0 + offset, 4000000.0, true, 33, 'S', false);
+            let west = Utm::new(500000.0 - offset, 4000000.0, true, 33, 'S', false);
+            let k = east.scale_factor(&datum);
+            assert!(k > previous);
+            assert!((k - west.scale_factor(&datum)).abs() < 1e-12);
+            previous = k;
+        }
+        assert!(previous > 1.0009);
+    }
+
+    #[test]
+    fn ground_distance() {
+        // Along a parallel, the ellipsoidal arc is within millimeters of the
+        // geodesic for these 50 km lines
+        let datum = Datum::wgs84();
+        let phi = (-23.0_f64).to_radians();
+        let nu = datum.a / (1.0 - datum.e2 * phi.sin().powi(2)).sqrt();
+
+        for &(lon1, lon2) in [(-48.0, -47.5), (-45.2, -44.8), (-42.5, -42.0)].iter() {
+            let a: Utm = Coord::new(-23.0, lon1).into();
+            let b: Utm = Coord::new(-23.0, lon2).into();
+            let arc = nu * phi.cos() * (lon2 - lon1).to_radians();
+            let grid = (a.easting - b.easting).hypot(a.northing - b.northing);
+            let ground = a.ground_distance(&b).unwrap();
+            assert!((ground - arc).abs() < 0.5);
+            assert!((ground - arc).abs() < (grid - arc).abs());
+
+            // The spherical haversine distance agrees within its own 0.5%
+            let haversine = Coord::new(-23.0, lon1).haversine_distance(&Coord::new(-23.0, lon2));
+            assert!((ground - haversine).abs() / haversine < 0.005);
+        }
+
+        // A diagonal line, with a different scale factor at each end
+        let (c, d) = (Coord::new(-23.4, -45.6), Coord::new(-22.9, -45.1));
+        let (a, b): (Utm, Utm) = (c.into(), d.into());
+        let haversine = c.haversine_distance(&d);
+        let ground = a.ground_distance(&b).unwrap();
+        assert!((ground - haversine).abs() / haversine < 0.005);
+
+        let a = Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+        let b = Utm::new(339735.0, 7454564.0, false, 24, 'K', false);
+        assert_eq!(
+            a.ground_distance(&b).unwrap_err(),
+            UtmError::SpansMultipleZones(23, 24)
+        );
+    }
+
+    #[test]
+    fn lon_change_for_easting() {
+        let equator = Utm::new(500000.0, 0.0, true, 23, 'N', false);
+        assert!((equator.lon_change_for_easting(111320.0) - 1.0).abs() < 0.001);
+        assert!((equator.lon_change_for_easting(-111320.0) + 1.0).abs() < 0.001);
+        assert_eq!(equator.lon_change_for_easting(0.0), 0.0);
+
+        // Meridians converge, so the same shift spans more longitude
+        let rio = Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+        let dlon = rio.lon_change_for_easting(1000.0);
+        assert!(dlon > 1000.0 / 111320.0);
+    }
+
+    #[test]
+    fn grid_azimuth() {
+        let a = Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+        let azimuth = |e: f64, n: f64| {
+            a.grid_azimuth(&Utm::new(e, n, false, 23, 'K', false))
+                .unwrap()
+        };
+        assert_eq!(azimuth(660265.0, 7455564.0), 0.0);
+        assert_eq!(azimuth(661265.0, 7454564.0), 90.0);
+        assert_eq!(azimuth(660265.0, 7453564.0), 180.0);
+        assert_eq!(azimuth(659265.0, 7454564.0), 270.0);
+        assert_eq!(azimuth(661265.0, 7455564.0), 45.0);
+
+        // Across the equator
+        let south = Utm::new(500000.0, 9999000.0, false, 23, 'M', false);
+        let north = Utm::new(500000.0, 1000.0, true, 23, 'N', false);
+        assert_eq!(south.grid_azimuth(&north).unwrap(), 0.0);
+
+        let other = Utm::new(339735.0, 7454564.0, false, 24, 'K', false);
+        assert_eq!(
+            a.grid_azimuth(&other).unwrap_err(),
+            UtmError::SpansMultipleZones(23, 24)
+        );
+    }
+
+    #[test]
+    fn true_to_grid_bearing() {
+        let utm = Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+        let gamma = utm.convergence(&Datum::wgs84());
+        assert!((utm.true_to_grid_bearing(90.0) - (90.0 - gamma)).abs() < 1e-9);
+        assert!((utm.true_to_grid_bearing(0.0) - (-gamma)).abs() < 1e-9);
+
+        let utm = Utm::new(500000.0, 4000000.0, true, 33, 'S', false);
+        assert!((utm.true_to_grid_bearing(45.0) - 45.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn declination_diagram_text() {
+        let utm = Utm::new(660265.0, 7454564.0, false, 23, 'K', false);
+        let text = utm.declination_diagram_text(-22.5, 2019);
+        assert_eq!(
+            text,
+            "Grid convergence: 0.6\u{b0} W\n\
+             Magnetic declination (2019): 22.5\u{b0} W\n\
+             GN-MN angle: 21.9\u{b0} W"
+        );
+    }
 }
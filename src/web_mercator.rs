@@ -0,0 +1,149 @@
+use crate::coord::Coord;
+use crate::datum::{wgs84_constants, Datum};
+use crate::math;
+use crate::projection::Projection;
+
+use std::fmt;
+
+/// Latitude limit, in degrees, of the square Web Mercator world
+pub const MAX_LATITUDE: f64 = 85.051_128_78;
+
+///
+/// Web Mercator (EPSG:3857) coordinates, in meters
+///
+/// Uses the spherical Mercator formulas over a sphere with the WGS84
+/// semi-major axis as radius. Latitudes beyond [`MAX_LATITUDE`] are clamped
+/// to it.
+///
+/// # Example
+/// ```
+/// use geomorph::*;
+///
+/// let coord = coord::Coord::new(-23.0095839, -43.4361816);
+/// let wm: web_mercator::WebMercator = coord.into();
+/// let back: coord::Coord = wm.into();
+/// assert!((back.lat - coord.lat).abs() < 1e-9);
+/// ```
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WebMercator {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl WebMercator {
+    /// Return a new WebMercator instance.
+    pub fn new(x: f64, y: f64) -> WebMercator {
+        WebMercator { x, y }
+    }
+}
+
+impl fmt::Display for WebMercator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({:.2}, {:.2})", self.x, self.y)
+    }
+}
+
+impl From<Coord> for WebMercator {
+    fn from(coord: Coord) -> Self {
+        let radius = wgs84_constants::SEMI_MAJOR_AXIS;
+        let lat = coord.lat.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+
+        WebMercator {
+            x: radius * coord.lon.to_radians(),
+            y: radius * lat.tan().asinh(),
+        }
+    }
+}
+
+impl From<WebMercator> for Coord {
+    fn from(wm: WebMercator) -> Self {
+        let radius = wgs84_constants::SEMI_MAJOR_AXIS;
+        let lat = (wm.y / radius).sinh().atan().to_degrees();
+        let lon = math::angle_normalize((wm.x / radius).to_degrees());
+
+        Coord::new(lat, lon)
+    }
+}
+
+/// Web Mercator is defined on a fixed sphere, so `datum` is ignored
+impl Projection for WebMercator {
+    fn project(coord: &Coord, _datum: &Datum) -> Self {
+        (*coord).into()
+    }
+
+    fn unproject(&self, _datum: &Datum) -> Coord {
+        (*self).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn origin() {
+        let wm: WebMercator = Coord::new(0.0, 0.0).into();
+        assert_eq!(wm, WebMercator::new(0.0, 0.0));
+
+        let coord: Coord = WebMercator::new(0.0, 0.0).into();
+        assert_eq!((coord.lat, coord.lon), (0.0, 0.0));
+    }
+
+    #[test]
+    fn from_coord() {
+        // Half the equator circumference at the antimeridian, and the same
+        // extent north at the latitude limit
+        let edge = std::f64::consts::PI * wgs84_constants::SEMI_MAJOR_AXIS;
+        let wm: WebMercator = Coord::new(MAX_LATITUDE, 180.0).into();
+        assert!((wm.x - edge).abs() < 1e-6);
+        assert!((wm.y - edge).abs() < 0.01);
+
+        let wm: WebMercator = Coord::new(-23.0095839, -43.4361816).into();
+        assert!((wm.x + 4835293.62).abs() < 0.01);
+        assert!((wm.y + 2633177.69).abs() < 0.01);
+    }
+
+    #[test]
+    fn antimeridian() {
+        let east: WebMercator = Coord::new(10.0, 180.0).into();
+        let west: WebMercator = Coord::new(10.0, -180.0).into();
+        assert_eq!(east.x, -west.x);
+
+        let coord: Coord = west.into();
+        assert!((coord.lon.abs() - 180.0).abs() < 1e-9);
+        let coord: Coord = WebMercator::new(-west.x * 1.5, 0.0).into();
+        assert!((coord.lon + 90.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn clamped_at_poles() {
+        let north: WebMercator = Coord::new(90.0, 0.0).into();
+        let limit: WebMercator = Coord::new(MAX_LATITUDE, 0.0).into();
+        assert!(north.y.is_finite());
+        assert_eq!(north.y, limit.y);
+
+        let south: WebMercator = Coord::new(-90.0, 0.0).into();
+        assert_eq!(south.y, -limit.y);
+    }
+
+    #[test]
+    fn round_trip() {
+        let coords = [
+            Coord::new(-23.0095839, -43.4361816),
+            Coord::new(52.52, 13.40),
+            Coord::new(-85.0, 179.9),
+            Coord::new(85.0, -179.9),
+        ];
+
+        for coord in coords.iter() {
+            let back: Coord = WebMercator::from(*coord).into();
+            assert!((back.lat - coord.lat).abs() < 1e-9, "{} {}", coord, back);
+            assert!((back.lon - coord.lon).abs() < 1e-9, "{} {}", coord, back);
+
+            let projected = WebMercator::project(coord, &Datum::grs80());
+            assert_eq!(projected, WebMercator::from(*coord));
+        }
+    }
+}